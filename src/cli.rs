@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Watch slack channels and tell when something needs your attention"
+)]
+pub struct Cli {
+    /// TOML configuration file. Defaults to SLACK_CONFIG or ./slack_notifier.toml when present
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Override a configuration key, e.g. --set watch.interval_secs=60. Repeatable
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    pub overrides: Vec<String>,
+    /// Folder with the cache files. Same as --set storage.dir=<DIR>
+    #[arg(long, global = true)]
+    pub storage_dir: Option<PathBuf>,
+    /// Environment variable holding the slack token
+    #[arg(long, global = true, default_value = "SLACK_TOKEN")]
+    pub token_env: String,
+    /// File holding the slack token. Takes precedence over --token-env
+    #[arg(long, global = true)]
+    pub token_file: Option<PathBuf>,
+    /// What to do, watch when missing
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Poll the channels and tell when something needs attention
+    Watch(WatchArgs),
+    /// Manage the channels cache
+    Channels {
+        #[command(subcommand)]
+        action: ChannelsCommand,
+    },
+    /// Manage the users cache
+    Users {
+        #[command(subcommand)]
+        action: UsersCommand,
+    },
+    /// List or acknowledge the notified events still pending
+    Pending {
+        #[command(subcommand)]
+        action: PendingCommand,
+    },
+    /// Print the latest messages of a channel
+    History(HistoryArgs),
+    /// Print every message of a thread
+    Thread(ThreadArgs),
+    /// Full text search over the local message archive
+    Search(SearchArgs),
+    /// Check the token scopes and the cache files
+    Doctor,
+    /// Write a channel's history, threads included, to a file
+    Export(ExportArgs),
+    /// Print the activity digest since the previous one
+    Digest(DigestArgs),
+}
+
+#[derive(Debug, Default, Args)]
+pub struct WatchArgs {
+    /// Run a single cycle, print a json summary and exit with status 10 when it should notify
+    #[arg(long)]
+    pub once: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DigestArgs {
+    /// Cover the last hours instead of the time since the previous digest
+    #[arg(long)]
+    pub hours: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ChannelsCommand {
+    /// Print the cached channels
+    List,
+    /// Reload the channels from slack, keeping ignored and manually added ones
+    Refresh,
+    /// Skip a channel when watching
+    Ignore {
+        /// Channel id or name
+        channel: String,
+    },
+    /// Watch a previously ignored channel
+    Unignore {
+        /// Channel id or name
+        channel: String,
+    },
+    /// Watch a channel slack does not list
+    Add {
+        /// Channel id
+        channel_id: String,
+        /// Name to show
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UsersCommand {
+    /// Print the cached users
+    List,
+    /// Notify when the user is involved in a message
+    Follow {
+        /// User id or name
+        user: String,
+    },
+    /// Stop notifying for the user
+    Unfollow {
+        /// User id or name
+        user: String,
+    },
+    /// Reload the users from slack, keeping who is followed
+    Refresh,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PendingCommand {
+    /// Print the events nobody acknowledged yet
+    List,
+    /// Acknowledge an event
+    Ack {
+        /// Event id as listed, or a message ts to acknowledge all its events
+        id: String,
+    },
+    /// Acknowledge every event of a channel
+    AckChannel {
+        /// Channel id or name
+        channel: String,
+    },
+    /// Acknowledge every event
+    AckAll,
+}
+
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// Channel id or name
+    pub channel: String,
+    /// Number of messages
+    #[arg(long, short, default_value_t = 20)]
+    pub limit: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct ThreadArgs {
+    /// Channel id or name
+    pub channel: String,
+    /// ts of the thread parent message
+    pub ts: String,
+}
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// Words to look for. All of them must be present
+    pub text: Vec<String>,
+    /// Channel id or name
+    #[arg(long, short)]
+    pub channel: Option<String>,
+    /// Sender user id or name
+    #[arg(long, short)]
+    pub from: Option<String>,
+    /// First day to include, YYYY-MM-DD
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Last day to include, YYYY-MM-DD
+    #[arg(long)]
+    pub until: Option<String>,
+    /// Only messages mentioning notify.user_id (or SLACK_USER_ID)
+    #[arg(long)]
+    pub mentions_me: bool,
+    /// Only messages that belong to a thread
+    #[arg(long)]
+    pub in_thread: bool,
+    /// Maximum number of results
+    #[arg(long, default_value_t = 20)]
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// One raw slack message per line
+    Jsonl,
+    Markdown,
+    /// Self contained html page
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Channel id or name
+    pub channel: String,
+    /// First day to include, YYYY-MM-DD
+    #[arg(long)]
+    pub since: String,
+    /// Last day to include, YYYY-MM-DD. Defaults to now
+    #[arg(long)]
+    pub until: Option<String>,
+    #[arg(long, short, value_enum, default_value_t = ExportFormat::Markdown)]
+    pub format: ExportFormat,
+    /// Destination file. Defaults to <channel>.<format extension>
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
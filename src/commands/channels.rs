@@ -0,0 +1,58 @@
+use crate::conversation::entity::channels_service::Channel;
+use crate::conversation::ids_str::ChannelId;
+use crate::conversation::services::channels_cache_fs::{read_cache, ChannelStorage};
+
+fn print_channels(channels: &[ChannelStorage]) {
+    for c in channels {
+        let mut flags = Vec::new();
+        if c.custom {
+            flags.push("added");
+        }
+        if c.ignore {
+            flags.push("ignored");
+        }
+        println!("{} #{} {}", c.channel_id, c.name, flags.join(","));
+    }
+    println!("{} channels", channels.len());
+}
+
+pub fn list() -> Result<(), String> {
+    match read_cache() {
+        Ok(cached) => {
+            print_channels(&cached);
+            Ok(())
+        }
+        Err(_) => Err("There is no channels cache yet, run `channels refresh`".into()),
+    }
+}
+
+pub async fn refresh() -> Result<(), String> {
+    match Channel::refresh_slack_channels().await {
+        Ok(refreshed) => {
+            print_channels(&refreshed);
+            Ok(())
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn ignore(channel: &str, ignore: bool) -> Result<(), String> {
+    let channel = channel.trim_start_matches('#');
+    match Channel::set_ignored(channel, ignore) {
+        Ok(c) if ignore => println!("#{} will be skipped", c.name),
+        Ok(c) => println!("#{} will be watched", c.name),
+        Err(e) => return Err(e.to_string()),
+    }
+
+    Ok(())
+}
+
+pub fn add(channel_id: &str, name: &str) -> Result<(), String> {
+    let channel_id = ChannelId::parse(channel_id).map_err(|e| e.to_string())?;
+    match Channel::add_custom(channel_id, name.trim_start_matches('#')) {
+        Ok(c) => println!("Added #{} ({})", c.name, c.channel_id),
+        Err(e) => return Err(e.to_string()),
+    }
+
+    Ok(())
+}
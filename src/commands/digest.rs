@@ -0,0 +1,40 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cli::DigestArgs;
+use crate::config;
+use crate::conversation::entity::channels_service::Channel;
+use crate::conversation::entity::digest_service::{Digest, DigestState};
+use crate::conversation::entity::users_service;
+use crate::conversation::entity::watch_service::TrackedThreads;
+
+// Seconds covered when there is no previous digest
+const DEFAULT_PERIOD: u64 = 24 * 3600;
+
+// Prints the digest without sending it nor moving the schedule.
+pub async fn run(args: DigestArgs) -> Result<(), String> {
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(n) => n.as_secs(),
+        Err(e) => return Err(e.to_string()),
+    };
+    let since = match args.hours {
+        Some(hours) => now.saturating_sub(hours * 3600),
+        None => match DigestState::load() {
+            Ok(s) if s.last_at > 0 => s.last_at,
+            _ => now.saturating_sub(DEFAULT_PERIOD),
+        },
+    };
+
+    let mut channels = match Channel::load_slack_channels().await {
+        Ok(c) => c,
+        Err(e) => return Err(e.to_string()),
+    };
+    channels.retain(|c| config::get().channels.selects(&c.channel_id, &c.name));
+    let me = users_service::my_user_id().await;
+
+    let tracked = TrackedThreads::load().unwrap_or_default().messages;
+
+    let digest = Digest::load("Slack digest", &channels, &tracked, since, now, me.as_ref()).await;
+    println!("{}", digest.text);
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use crate::conversation::entity::doctor_service::check_features;
+use crate::conversation::errors_str::FileSystemError;
+use crate::conversation::services::storage_path::{storage_dir, CacheCheck};
+use crate::conversation::services::{
+    archive_db::Archive, channels_cache_fs, slack_probe::get_auth_test, users_cache_fs,
+};
+
+const OK: &str = "\x1b[32m[ok]\x1b[0m     ";
+const FAIL: &str = "\x1b[93m[missing]\x1b[0m";
+const UNUSED: &str = "[unused] ";
+
+fn print_cache(name: &str, check: Result<CacheCheck, FileSystemError>) -> usize {
+    match check {
+        Ok(c) if c.problems.is_empty() => {
+            println!("{} {} {} entries", OK, name, c.entries);
+            0
+        }
+        Ok(c) => {
+            println!("{} {} {} entries", FAIL, name, c.entries);
+            for p in c.problems.iter() {
+                println!("            {}", p);
+            }
+            c.problems.len()
+        }
+        Err(e) => {
+            println!("{} {} {}", FAIL, name, e);
+            1
+        }
+    }
+}
+
+// Tells what the token can do and if the local files are readable.
+pub async fn run() -> Result<(), String> {
+    let mut problems = 0;
+
+    let (auth, scopes) = match get_auth_test().await {
+        Ok(a) => a,
+        Err(e) => return Err(format!("auth.test failed. {}", e)),
+    };
+    if !auth.ok {
+        return Err(format!(
+            "The token was rejected: {}",
+            auth.error.unwrap_or("unknown error".into())
+        ));
+    }
+    println!(
+        "Token for {} ({}) on {} ({}) {}",
+        auth.user.unwrap_or_default(),
+        auth.user_id.map(String::from).unwrap_or_default(),
+        auth.team.unwrap_or_default(),
+        auth.team_id.map(String::from).unwrap_or_default(),
+        auth.url.unwrap_or_default()
+    );
+    if scopes.is_empty() {
+        println!("Granted scopes: unknown, relying on the probes");
+    } else {
+        println!("Granted scopes: {}", scopes.join(", "));
+    }
+
+    println!("\nFeatures:");
+    for check in check_features(&scopes).await {
+        if check.is_ok() {
+            println!("{} {}", OK, check.feature);
+            continue;
+        }
+        let mut details = Vec::new();
+        if !check.missing_scopes.is_empty() {
+            details.push(format!("needs {}", check.missing_scopes.join(", ")));
+        }
+        if let Some(error) = check.error {
+            details.push(error);
+        }
+        // Only what the configuration uses is a problem
+        if check.enabled {
+            problems += 1;
+            println!("{} {}: {}", FAIL, check.feature, details.join(". "));
+        } else {
+            println!("{} {}: {}", UNUSED, check.feature, details.join(". "));
+        }
+    }
+
+    println!("\nFiles in {}:", storage_dir().display());
+    problems += print_cache("channels_cache.txt", channels_cache_fs::check_cache());
+    problems += print_cache("users_cache.txt", users_cache_fs::check_cache());
+    match Archive::from_config() {
+        Ok(Some(_)) => println!("{} message archive", OK),
+        Ok(None) => println!("{} message archive disabled", OK),
+        Err(e) => {
+            problems += 1;
+            println!("{} message archive {}", FAIL, e);
+        }
+    }
+
+    if problems > 0 {
+        return Err(format!("{} problems found", problems));
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use super::{day_start, find_channel};
+use crate::cli::{ExportArgs, ExportFormat};
+use crate::conversation::entity::export_service::ChannelExport;
+use crate::conversation::entity::mrkdwn_service::MrkdwnRenderer;
+
+pub async fn run(args: ExportArgs) -> Result<(), String> {
+    let since = day_start(&args.since, 0)? as u64;
+    let until = match &args.until {
+        Some(d) => Some(day_start(d, 1)? as u64),
+        None => None,
+    };
+
+    let channel = find_channel(&args.channel).await?;
+    println!("Exporting #{} since {}", channel.name, args.since);
+    let export = match ChannelExport::load(channel, since, until).await {
+        Ok(e) => e,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let names = MrkdwnRenderer::from_caches();
+    let content = match args.format {
+        ExportFormat::Jsonl => export.to_jsonl(),
+        ExportFormat::Markdown => export.to_markdown(&names),
+        ExportFormat::Html => export.to_html(&names),
+    };
+
+    let output = args
+        .output
+        .unwrap_or(PathBuf::from(export.file_name(args.format.extension())));
+    if let Err(e) = std::fs::write(&output, content) {
+        return Err(format!("Error writing {}. {}", output.display(), e));
+    }
+
+    let replies: usize = export.threads.iter().map(|t| t.replies.len()).sum();
+    println!(
+        "Wrote {} messages and {} replies to {}",
+        export.threads.len(),
+        replies,
+        output.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use super::find_channel;
+use crate::cli::{HistoryArgs, ThreadArgs};
+use crate::conversation::entity::channels_service::{Channel, Message};
+use crate::conversation::entity::directory_service::UserDirectory;
+use crate::conversation::entity::mrkdwn_service::MrkdwnRenderer;
+use crate::conversation::ids_str::UserId;
+
+fn print_message(message: &Message, names: &MrkdwnRenderer) {
+    let replies = match &message.reply {
+        Some(r) => format!(
+            " [thread, last reply {}]",
+            Message::display_time(r.latest_reply)
+        ),
+        None => String::new(),
+    };
+    println!(
+        "{} {} {}: {}{}",
+        Message::display_time(message.received_at),
+        message.received_ts,
+        names.user_name(message.sender.as_deref().unwrap_or_default()),
+        names.to_terminal(&message.message),
+        replies
+    );
+}
+
+// Names for the senders, asking slack for the ones the cache does not know.
+async fn names_for(messages: &[Message]) -> MrkdwnRenderer {
+    let mut directory = UserDirectory::load();
+    let senders: Vec<&UserId> = messages.iter().filter_map(|m| m.sender.as_ref()).collect();
+    directory.lookup(&senders).await;
+
+    MrkdwnRenderer::from_directory(&directory)
+}
+
+pub async fn history(args: HistoryArgs) -> Result<(), String> {
+    let channel = find_channel(&args.channel).await?;
+    let messages = match channel.load_history(args.limit).await {
+        Ok(m) => m,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let names = names_for(&messages).await;
+    println!("#{}", channel.name);
+    for message in messages.iter() {
+        print_message(message, &names);
+    }
+
+    Ok(())
+}
+
+pub async fn thread(args: ThreadArgs) -> Result<(), String> {
+    let channel = find_channel(&args.channel).await?;
+    let thread = match Channel::load_thread(&channel.channel_id, &args.ts).await {
+        Ok(t) => t,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let messages: Vec<Message> = thread.iter().map(|m| m.into()).collect();
+    let names = names_for(&messages).await;
+    println!("#{} thread {}", channel.name, args.ts);
+    for message in messages.iter() {
+        print_message(message, &names);
+    }
+
+    Ok(())
+}
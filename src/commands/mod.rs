@@ -0,0 +1,50 @@
+use chrono::{Local, NaiveDate, TimeZone};
+
+use crate::conversation::entity::channels_service::Channel;
+use crate::conversation::ids_str::ChannelId;
+
+pub mod channels;
+pub mod digest;
+pub mod doctor;
+pub mod export;
+pub mod history;
+pub mod pending;
+pub mod search;
+pub mod users;
+pub mod watch;
+
+// Seconds at the local midnight starting the given YYYY-MM-DD day.
+pub fn day_start(day: &str, days_after: u64) -> Result<i64, String> {
+    let date = match NaiveDate::parse_from_str(day, "%Y-%m-%d") {
+        Ok(d) => d,
+        Err(_) => return Err(format!("Invalid date {}, expected YYYY-MM-DD", day)),
+    };
+    let date = date + chrono::Days::new(days_after);
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+
+    match Local.from_local_datetime(&midnight).earliest() {
+        Some(d) => Ok(d.timestamp()),
+        None => Err(format!("Invalid local date {}", day)),
+    }
+}
+
+// Looks the channel up by id or name, unknown ids are used as they are.
+pub async fn find_channel(channel: &str) -> Result<Channel, String> {
+    let channel = channel.trim_start_matches('#');
+    let known = match Channel::load_slack_channels().await {
+        Ok(c) => c,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    if let Some(found) = known
+        .into_iter()
+        .find(|c| c.channel_id == channel || c.name == channel)
+    {
+        return Ok(found);
+    }
+    if let Ok(channel_id) = ChannelId::parse(channel) {
+        return Ok(Channel::new(channel.into(), channel_id, false));
+    }
+
+    Err(format!("Unknown channel {}", channel))
+}
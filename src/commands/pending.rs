@@ -0,0 +1,65 @@
+use crate::conversation::entity::notify_service::Notifiers;
+use crate::conversation::entity::pending_service::PendingEvents;
+
+fn load() -> Result<PendingEvents, String> {
+    PendingEvents::load().map_err(|e| e.to_string())
+}
+
+pub fn list() -> Result<(), String> {
+    let pending = load()?;
+    for p in pending.events() {
+        let e = &p.event;
+        let text: String = e.text.chars().take(80).collect();
+        println!(
+            "{} [{:?}] {:?} in #{} by {}: {}",
+            e.id(),
+            e.priority,
+            e.reason,
+            if e.channel_name.is_empty() {
+                e.channel_id.as_str()
+            } else {
+                e.channel_name.as_str()
+            },
+            e.sender_label(),
+            text.replace('\n', " ")
+        );
+    }
+    println!("{} pending", pending.events().len());
+
+    Ok(())
+}
+
+pub fn ack(id: &str) -> Result<(), String> {
+    save(|p| p.ack(id))
+}
+
+pub fn ack_channel(channel: &str) -> Result<(), String> {
+    save(|p| p.ack_channel(channel))
+}
+
+pub fn ack_all() -> Result<(), String> {
+    save(|p| p.ack_all())
+}
+
+// Saves and tells the notifiers right away, the led does not wait for the next cycle.
+fn save(acknowledge: impl FnOnce(&mut PendingEvents) -> usize) -> Result<(), String> {
+    let mut pending = load()?;
+    let acknowledged = acknowledge(&mut pending);
+    if acknowledged == 0 {
+        return Err("Nothing pending matches".into());
+    }
+    if let Err(e) = pending.save() {
+        return Err(e.to_string());
+    }
+
+    let mut notifiers = Notifiers::from_config(false);
+    notifiers.state(&pending.notify_events());
+    notifiers.close();
+    println!(
+        "{} acknowledged, {} pending",
+        acknowledged,
+        pending.events().len()
+    );
+
+    Ok(())
+}
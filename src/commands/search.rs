@@ -0,0 +1,51 @@
+use super::day_start;
+use crate::cli::SearchArgs;
+use crate::config;
+use crate::conversation::entity::channels_service::Message;
+use crate::conversation::entity::mrkdwn_service::MrkdwnRenderer;
+use crate::conversation::entity::search_service::{search_messages, SearchQuery};
+
+pub fn run(args: SearchArgs) -> Result<(), String> {
+    let mentions = if args.mentions_me {
+        match &config::get().notify.user_id {
+            Some(id) => Some(id.to_string()),
+            None => {
+                return Err("--mentions-me needs notify.user_id or SLACK_USER_ID to be set".into())
+            }
+        }
+    } else {
+        None
+    };
+
+    let query = SearchQuery {
+        text: Some(args.text.join(" ")),
+        channel: args.channel,
+        sender: args.from,
+        since: args.since.as_deref().map(|d| day_start(d, 0)).transpose()?,
+        until: args.until.as_deref().map(|d| day_start(d, 1)).transpose()?,
+        mentions,
+        in_thread: args.in_thread,
+        limit: args.limit,
+    };
+
+    let hits = match search_messages(&query) {
+        Ok(h) => h,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    if hits.is_empty() {
+        println!("No messages found.");
+    }
+    let names = MrkdwnRenderer::from_caches();
+    for hit in hits {
+        println!(
+            "{} #{} {}: {}",
+            Message::display_time(hit.message.received_at),
+            hit.channel.name,
+            hit.author.as_ref().map(|a| a.name()).unwrap_or_default(),
+            names.to_terminal(&hit.message.message)
+        );
+    }
+
+    Ok(())
+}
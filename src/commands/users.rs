@@ -0,0 +1,35 @@
+use crate::conversation::entity::{users::User, users_service};
+
+fn print_users(users: &[User]) {
+    for u in users {
+        let follow = if u.should_follow { "following" } else { "" };
+        println!("{} {} {}", u.id(), u.name(), follow);
+    }
+    println!("{} users", users.len());
+}
+
+pub fn list() -> Result<(), String> {
+    print_users(&users_service::load_slack_users());
+
+    Ok(())
+}
+
+pub fn follow(user: &str, should_follow: bool) -> Result<(), String> {
+    match users_service::set_follow(user.trim_start_matches('@'), should_follow) {
+        Ok(u) if should_follow => println!("Following {} ({})", u.name(), u.id()),
+        Ok(u) => println!("Not following {} ({})", u.name(), u.id()),
+        Err(e) => return Err(e.to_string()),
+    }
+
+    Ok(())
+}
+
+pub async fn refresh() -> Result<(), String> {
+    match users_service::refresh_slack_users().await {
+        Ok(refreshed) => {
+            print_users(&refreshed);
+            Ok(())
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
@@ -0,0 +1,59 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::cli::WatchArgs;
+use crate::config;
+use crate::conversation::entity::notify_service::Notifiers;
+use crate::conversation::entity::watch_service::Watcher;
+
+// Exit status of `watch --once` when a notification is warranted
+pub const NOTIFY_EXIT_CODE: i32 = 10;
+
+pub async fn run(args: WatchArgs) -> Result<(), String> {
+    let mut watcher = match Watcher::new(!args.once).await {
+        Ok(w) => w,
+        Err(e) => return Err(format!("There was an error loading {:?}", e)),
+    };
+
+    // The json summary is the console output of --once
+    let mut notifiers = Notifiers::from_config(!args.once);
+
+    if args.once {
+        let summary = watcher.run_cycle().await;
+        notifiers.escalate(&summary.escalated);
+        notifiers.dispatch(&summary.events, &watcher.pending());
+        // Cron runs send the digest too, its state file says when the last one went out
+        if let Some(digest) = watcher.digest_if_due().await {
+            notifiers.digest(&digest, &config::get().digest.notifiers);
+        }
+        notifiers.close();
+        match serde_json::to_string(&summary) {
+            Ok(json) => println!("{}", json),
+            Err(e) => return Err(e.to_string()),
+        }
+        if summary.should_notify {
+            std::process::exit(NOTIFY_EXIT_CODE);
+        }
+        return Ok(());
+    }
+
+    println!("Starting fetch data!");
+    if let Some(report) = watcher.catch_up().await {
+        let kinds = &config::get().digest.notifiers;
+        // Printed either way, the console would print it twice
+        if !kinds.iter().any(|k| k == "console") {
+            println!("{}", report.text);
+        }
+        notifiers.digest(&report, kinds);
+    }
+    loop {
+        let summary = watcher.run_cycle().await;
+        notifiers.escalate(&summary.escalated);
+        notifiers.dispatch(&summary.events, &watcher.pending());
+        if let Some(digest) = watcher.digest_if_due().await {
+            notifiers.digest(&digest, &config::get().digest.notifiers);
+        }
+
+        thread::sleep(Duration::from_secs(config::get().watch.interval_secs));
+    }
+}
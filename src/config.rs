@@ -0,0 +1,844 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::conversation::entity::notification::Priority;
+use crate::conversation::entity::quiet_hours_service::QuietHours;
+use crate::conversation::entity::rules_service::Rule;
+use crate::conversation::errors_str::ConfigError;
+use crate::conversation::ids_str::{TeamId, UserId};
+
+// Used when neither --config nor SLACK_CONFIG are given, if it exists.
+static DEFAULT_CONFIG_FILE: &str = "slack_notifier.toml";
+static CONFIG_ENV: &str = "SLACK_CONFIG";
+// SLACK_NOTIFIER__WATCH__INTERVAL_SECS=60 overrides watch.interval_secs
+static ENV_OVERRIDE_PREFIX: &str = "SLACK_NOTIFIER__";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub storage: StorageConfig,
+    pub watch: WatchConfig,
+    pub history: HistoryConfig,
+    pub channels: ChannelsConfig,
+    pub notify: NotifyConfig,
+    pub quiet_hours: QuietHoursConfig,
+    pub escalation: EscalationConfig,
+    pub digest: DigestConfig,
+    // [[rules]] entries, keyword and pattern notifications
+    pub rules: Vec<RuleConfig>,
+    // [[notifiers]] entries, where the events go
+    pub notifiers: Vec<NotifierConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            storage: StorageConfig::default(),
+            watch: WatchConfig::default(),
+            history: HistoryConfig::default(),
+            channels: ChannelsConfig::default(),
+            notify: NotifyConfig::default(),
+            quiet_hours: QuietHoursConfig::default(),
+            escalation: EscalationConfig::default(),
+            digest: DigestConfig::default(),
+            rules: Vec::new(),
+            notifiers: vec![NotifierConfig::Console {
+                min_priority: Priority::Low,
+            }],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    // Folder with the cache files
+    pub dir: PathBuf,
+    // Sqlite file for the message archive, disabled when empty
+    pub archive: Option<PathBuf>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            dir: PathBuf::from("static/storage"),
+            archive: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchConfig {
+    // Seconds between polling cycles
+    pub interval_secs: u64,
+    // Messages whose replies keep being followed
+    pub tracked_threads: usize,
+    // Report what happened while the watcher was off when it starts
+    pub catch_up: bool,
+    // Longest period the catch-up covers
+    pub max_catch_up_secs: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            interval_secs: 300,
+            tracked_threads: 30,
+            catch_up: true,
+            max_catch_up_secs: 24 * 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    // Messages per conversations.history query
+    pub limit: u32,
+    // How far back a cycle looks for new messages
+    pub lookback_secs: u64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            limit: 10,
+            lookback_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelsConfig {
+    pub exclude_archived: bool,
+    // Channel ids or names. When not empty only these are watched
+    pub include: Vec<String>,
+    // Channel ids or names that are never watched
+    pub exclude: Vec<String>,
+    // Conversation types listed. Those whose read scope is not granted are left out
+    pub types: Vec<ConversationType>,
+}
+
+impl Default for ChannelsConfig {
+    fn default() -> Self {
+        ChannelsConfig {
+            exclude_archived: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            types: vec![
+                ConversationType::PublicChannel,
+                ConversationType::PrivateChannel,
+                ConversationType::Im,
+                ConversationType::Mpim,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationType {
+    PublicChannel,
+    PrivateChannel,
+    // Direct messages
+    Im,
+    // Group direct messages
+    Mpim,
+}
+
+impl ConversationType {
+    // The conversations.list `types` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConversationType::PublicChannel => "public_channel",
+            ConversationType::PrivateChannel => "private_channel",
+            ConversationType::Im => "im",
+            ConversationType::Mpim => "mpim",
+        }
+    }
+
+    // Scope conversations.list needs to list the type
+    pub fn read_scope(&self) -> &'static str {
+        match self {
+            ConversationType::PublicChannel => "channels:read",
+            ConversationType::PrivateChannel => "groups:read",
+            ConversationType::Im => "im:read",
+            ConversationType::Mpim => "mpim:read",
+        }
+    }
+}
+
+impl ChannelsConfig {
+    pub fn selects(&self, channel_id: &str, name: &str) -> bool {
+        let listed = |list: &Vec<String>| {
+            list.iter()
+                .any(|c| c == channel_id || c.trim_start_matches('#') == name)
+        };
+
+        (self.include.is_empty() || listed(&self.include)) && !listed(&self.exclude)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    // Your own user id, for direct mentions. The token's user when it can be read
+    pub user_id: Option<UserId>,
+    // Workspace url, ie. https://acme.slack.com, and id for the links when auth.test can not be read
+    pub team_url: Option<String>,
+    pub team_id: Option<TeamId>,
+    // User ids followed on top of the users cache
+    pub users: Vec<UserId>,
+    // Notify on new messages involving followed users
+    pub on_messages: bool,
+    // Notify on new replies in threads involving followed users
+    pub on_thread_replies: bool,
+    // Notify on every message of direct and group direct messages
+    pub on_direct_messages: bool,
+    // Seconds before the same thread and reason notify again
+    pub cooldown_secs: u64,
+    // Acknowledge pending events you replied to in their thread or reacted to
+    pub auto_ack: bool,
+    // Reaction names, without colons, that acknowledge a message
+    pub ack_reactions: Vec<String>,
+    // Pending threads read per cycle for the acknowledgements, the longest unchecked first
+    pub ack_max_threads: usize,
+    pub priorities: PrioritiesConfig,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            user_id: None,
+            team_url: None,
+            team_id: None,
+            users: Vec::new(),
+            on_messages: true,
+            on_thread_replies: true,
+            on_direct_messages: true,
+            cooldown_secs: 1800,
+            auto_ack: true,
+            ack_reactions: vec!["eyes".into(), "white_check_mark".into()],
+            ack_max_threads: 10,
+            priorities: PrioritiesConfig::default(),
+        }
+    }
+}
+
+// Priority of the events for each reason.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrioritiesConfig {
+    pub direct_mention: Priority,
+    pub direct_message: Priority,
+    pub thread_reply: Priority,
+    pub keyword: Priority,
+    pub vip_sender: Priority,
+    pub broadcast: Priority,
+}
+
+impl Default for PrioritiesConfig {
+    fn default() -> Self {
+        PrioritiesConfig {
+            direct_mention: Priority::Urgent,
+            direct_message: Priority::Urgent,
+            thread_reply: Priority::Normal,
+            keyword: Priority::Normal,
+            vip_sender: Priority::Normal,
+            broadcast: Priority::Low,
+        }
+    }
+}
+
+// When notifications are held back.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuietHoursConfig {
+    // IANA name, ie. Europe/Madrid. The system time zone when missing
+    pub timezone: Option<String>,
+    pub ranges: Vec<QuietRangeConfig>,
+    pub action: QuietAction,
+    pub urgent_breaks_through: bool,
+    // Quiet too while your slack do not disturb is on
+    pub honour_slack_dnd: bool,
+}
+
+impl Default for QuietHoursConfig {
+    fn default() -> Self {
+        QuietHoursConfig {
+            timezone: None,
+            ranges: Vec::new(),
+            action: QuietAction::Suppress,
+            urgent_breaks_through: true,
+            honour_slack_dnd: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuietRangeConfig {
+    // mon, tue... Every day when empty
+    pub days: Vec<String>,
+    // HH:MM. An end before the start continues the next day
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuietAction {
+    // Events are dropped
+    Suppress,
+    // Events lose one priority level
+    Downgrade,
+}
+
+// Steps for the pending events of each priority, [[escalation.urgent]] entries and so on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EscalationConfig {
+    pub urgent: Vec<EscalationStep>,
+    pub normal: Vec<EscalationStep>,
+    pub low: Vec<EscalationStep>,
+}
+
+impl EscalationConfig {
+    pub fn steps(&self, priority: Priority) -> &[EscalationStep] {
+        match priority {
+            Priority::Urgent => &self.urgent,
+            Priority::Normal => &self.normal,
+            Priority::Low => &self.low,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EscalationStep {
+    // Seconds since the event was notified
+    pub after_secs: u64,
+    // Notifier types the event is sent to again, whatever their min_priority.
+    // Empty only raises the state's escalation level
+    pub notifiers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestSchedule {
+    #[default]
+    Off,
+    Hourly,
+    // Every day at daily_at
+    Daily,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFormat {
+    #[default]
+    Text,
+    Markdown,
+}
+
+// Activity summary sent while watching.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DigestConfig {
+    pub schedule: DigestSchedule,
+    // HH:MM, local time
+    pub daily_at: String,
+    pub format: DigestFormat,
+    // Notifier types the digest goes to
+    pub notifiers: Vec<String>,
+    // Appended with every digest, relative to the storage folder
+    pub file: Option<String>,
+    // How many of the users who reacted the most are listed
+    pub top_reactors: usize,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        DigestConfig {
+            schedule: DigestSchedule::Off,
+            daily_at: "09:00".into(),
+            format: DigestFormat::Text,
+            notifiers: vec!["console".into()],
+            file: None,
+            top_reactors: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleConfig {
+    // Shown as the notification reason
+    pub name: String,
+    // Literal text
+    pub keywords: Vec<String>,
+    // Regular expressions
+    pub patterns: Vec<String>,
+    // Channel ids or names. With channel_pattern empty too the rule applies everywhere
+    pub channels: Vec<String>,
+    // Regular expression over the channel name
+    pub channel_pattern: Option<String>,
+    pub case_insensitive: bool,
+    // Keywords and patterns only match complete words
+    pub whole_word: bool,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        RuleConfig {
+            name: String::new(),
+            keywords: Vec::new(),
+            patterns: Vec::new(),
+            channels: Vec::new(),
+            channel_pattern: None,
+            case_insensitive: true,
+            whole_word: false,
+        }
+    }
+}
+
+// A notification backend. Each one only gets events of min_priority and above.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NotifierConfig {
+    Console {
+        #[serde(default)]
+        min_priority: Priority,
+    },
+    // Appends every event as a json line
+    File {
+        path: String,
+        #[serde(default)]
+        min_priority: Priority,
+    },
+    // Runs `sh -c <command>` per event, with the event as json on stdin
+    Command {
+        command: String,
+        #[serde(default)]
+        min_priority: Priority,
+        // Seconds before a command still running is killed
+        #[serde(default = "command_timeout_secs")]
+        timeout_secs: u64,
+    },
+    Mqtt(MqttConfig),
+    Webhook(WebhookConfig),
+}
+
+fn command_timeout_secs() -> u64 {
+    30
+}
+
+impl NotifierConfig {
+    // The `type` in the configuration file.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NotifierConfig::Console { .. } => "console",
+            NotifierConfig::File { .. } => "file",
+            NotifierConfig::Command { .. } => "command",
+            NotifierConfig::Mqtt(_) => "mqtt",
+            NotifierConfig::Webhook(_) => "webhook",
+        }
+    }
+}
+
+// Publishes the events and a retained state message to a broker.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // Retained, the latest NotifyState
+    pub state_topic: String,
+    // One message per event
+    pub event_topic: String,
+    // One message per digest
+    pub digest_topic: String,
+    // 0, 1 or 2
+    pub qos: u8,
+    pub min_priority: Priority,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            host: "localhost".into(),
+            port: 1883,
+            client_id: "slack-notifier".into(),
+            username: None,
+            password: None,
+            state_topic: "slack/notifier/state".into(),
+            event_topic: "slack/notifier/events".into(),
+            digest_topic: "slack/notifier/digest".into(),
+            qos: 1,
+            min_priority: Priority::Low,
+        }
+    }
+}
+
+// POSTs every event as json, signed, to each url.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    // HMAC-SHA256 key, or the variable holding it
+    pub secret: Option<String>,
+    pub secret_env: Option<String>,
+    // Attempts after the first one
+    pub retries: u32,
+    // Doubled on every retry
+    pub backoff_ms: u64,
+    pub timeout_secs: u64,
+    // Characters of the message text sent
+    pub snippet_chars: usize,
+    // Events that could not be delivered, one json per line. Inside storage.dir when relative
+    pub failures_file: String,
+    pub min_priority: Priority,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        WebhookConfig {
+            urls: Vec::new(),
+            secret: None,
+            secret_env: None,
+            retries: 3,
+            backoff_ms: 500,
+            timeout_secs: 10,
+            snippet_chars: 200,
+            failures_file: "webhook_failures.jsonl".into(),
+            min_priority: Priority::Low,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn signing_secret(&self) -> Option<String> {
+        match &self.secret_env {
+            Some(name) => std::env::var(name).ok(),
+            None => self.secret.clone(),
+        }
+    }
+}
+
+impl Config {
+    // Defaults, then the config file, then the environment, then the command line.
+    pub fn load(file: Option<&Path>, cli_overrides: &[String]) -> Result<Config, ConfigError> {
+        let explicit = file
+            .map(|f| f.to_path_buf())
+            .or(std::env::var(CONFIG_ENV).ok().map(PathBuf::from));
+        let mut table = match &explicit {
+            Some(path) => read_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                read_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => toml::Table::new(),
+        };
+
+        for (key, value) in env_overrides() {
+            set_key(&mut table, &key, &value, "environment")?;
+        }
+        for item in cli_overrides {
+            let (key, value) = match item.split_once('=') {
+                Some(kv) => kv,
+                None => {
+                    return Err(ConfigError::new(&format!(
+                        "--set {}: expected key=value",
+                        item
+                    )))
+                }
+            };
+            set_key(&mut table, key.trim(), value.trim(), "--set")?;
+        }
+
+        let config = match Config::deserialize(toml::Value::Table(table)) {
+            Ok(c) => c,
+            Err(e) => return Err(ConfigError::new(&e.to_string().replace('\n', " "))),
+        };
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    // Checks the values serde can not, naming the offending key.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let fail =
+            |key: &str, message: &str| Err(ConfigError::new(&format!("{}: {}", key, message)));
+
+        if self.watch.interval_secs < 10 {
+            return fail("watch.interval_secs", "must be at least 10 seconds");
+        }
+        if self.watch.tracked_threads == 0 || self.watch.tracked_threads > 1000 {
+            return fail("watch.tracked_threads", "must be between 1 and 1000");
+        }
+        if self.watch.max_catch_up_secs == 0 {
+            return fail("watch.max_catch_up_secs", "must be greater than 0");
+        }
+        if self.history.limit == 0 || self.history.limit > 1000 {
+            return fail("history.limit", "must be between 1 and 1000");
+        }
+        if self.history.lookback_secs == 0 {
+            return fail("history.lookback_secs", "must be greater than 0");
+        }
+        if self.channels.types.is_empty() {
+            return fail("channels.types", "needs at least one conversation type");
+        }
+        if self.storage.dir.as_os_str().is_empty() {
+            return fail("storage.dir", "can not be empty");
+        }
+        if let Some(url) = &self.notify.team_url {
+            if !url.starts_with("https://") {
+                return fail("notify.team_url", &format!("{} is not a url", url));
+            }
+        }
+        for reaction in self.notify.ack_reactions.iter() {
+            if reaction.is_empty() || reaction.contains([':', ' ']) {
+                return fail(
+                    "notify.ack_reactions",
+                    &format!(
+                        "{:?} is not a reaction name, write it without colons",
+                        reaction
+                    ),
+                );
+            }
+        }
+        if self.notify.ack_max_threads == 0 {
+            return fail("notify.ack_max_threads", "must be greater than 0");
+        }
+        QuietHours::compile(&self.quiet_hours)?;
+        for (i, rule) in self.rules.iter().enumerate() {
+            let key = format!("rules[{}]", i);
+            if rule.name.is_empty() {
+                return fail(&key, "name is required");
+            }
+            if rule.keywords.is_empty() && rule.patterns.is_empty() {
+                return fail(&key, "needs keywords or patterns");
+            }
+            if let Err(e) = Rule::compile(rule) {
+                return fail(&key, &e.to_string());
+            }
+        }
+        for (i, notifier) in self.notifiers.iter().enumerate() {
+            let key = format!("notifiers[{}]", i);
+            match notifier {
+                NotifierConfig::File { path, .. } if path.is_empty() => {
+                    return fail(&key, "path can not be empty")
+                }
+                NotifierConfig::Command { command, .. } if command.trim().is_empty() => {
+                    return fail(&key, "command can not be empty")
+                }
+                NotifierConfig::Command {
+                    timeout_secs: 0, ..
+                } => return fail(&format!("{}.timeout_secs", key), "must be greater than 0"),
+                NotifierConfig::Mqtt(mqtt) if mqtt.qos > 2 => {
+                    return fail(&format!("{}.qos", key), "must be 0, 1 or 2")
+                }
+                NotifierConfig::Mqtt(mqtt) if mqtt.host.is_empty() => {
+                    return fail(&format!("{}.host", key), "can not be empty")
+                }
+                NotifierConfig::Webhook(webhook) => {
+                    if webhook.urls.is_empty() {
+                        return fail(&format!("{}.urls", key), "needs at least one url");
+                    }
+                    if let Some(url) = webhook.urls.iter().find(|u| !u.starts_with("http")) {
+                        return fail(&format!("{}.urls", key), &format!("{} is not a url", url));
+                    }
+                    if let Some(name) = &webhook.secret_env {
+                        if std::env::var(name).is_err() {
+                            return fail(
+                                &format!("{}.secret_env", key),
+                                &format!("{} is not set", name),
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if chrono::NaiveTime::parse_from_str(&self.digest.daily_at, "%H:%M").is_err() {
+            return fail(
+                "digest.daily_at",
+                &format!("{:?} is not a HH:MM time", self.digest.daily_at),
+            );
+        }
+        if self.digest.schedule != DigestSchedule::Off {
+            if let Some(kind) = self
+                .digest
+                .notifiers
+                .iter()
+                .find(|k| !self.notifiers.iter().any(|n| n.kind() == k.as_str()))
+            {
+                return fail(
+                    "digest.notifiers",
+                    &format!("there is no {} notifier", kind),
+                );
+            }
+        }
+        for priority in [Priority::Urgent, Priority::Normal, Priority::Low] {
+            let mut previous = 0;
+            for (i, step) in self.escalation.steps(priority).iter().enumerate() {
+                let key = format!("escalation.{:?}[{}]", priority, i).to_lowercase();
+                if step.after_secs <= previous {
+                    return fail(
+                        &format!("{}.after_secs", key),
+                        "must be greater than the previous step's",
+                    );
+                }
+                previous = step.after_secs;
+                if let Some(kind) = step
+                    .notifiers
+                    .iter()
+                    .find(|k| !self.notifiers.iter().any(|n| n.kind() == k.as_str()))
+                {
+                    return fail(
+                        &format!("{}.notifiers", key),
+                        &format!("there is no {} notifier", kind),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn read_file(path: &Path) -> Result<toml::Table, ConfigError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ConfigError::new(&format!(
+                "Error reading {}. {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+
+    // Parsing into the structs first reports unknown keys with their line
+    if let Err(e) = toml::from_str::<Config>(&content) {
+        return Err(ConfigError::new(&format!("{}: {}", path.display(), e)));
+    }
+    match toml::from_str::<toml::Table>(&content) {
+        Ok(t) => Ok(t),
+        Err(e) => Err(ConfigError::new(&format!("{}: {}", path.display(), e))),
+    }
+}
+
+// Variables with their own name, then the SLACK_NOTIFIER__SECTION__KEY ones.
+fn env_overrides() -> Vec<(String, String)> {
+    let mut overrides = Vec::new();
+    if let Ok(dir) = std::env::var("SLACK_STORAGE_DIR") {
+        overrides.push(("storage.dir".to_string(), dir));
+    }
+    if let Ok(archive) = std::env::var("SLACK_ARCHIVE") {
+        overrides.push(("storage.archive".to_string(), archive));
+    }
+    if let Ok(user_id) = std::env::var("SLACK_USER_ID") {
+        overrides.push(("notify.user_id".to_string(), user_id));
+    }
+
+    let mut generic: Vec<(String, String)> = std::env::vars()
+        .filter_map(|(name, value)| {
+            let key = name.strip_prefix(ENV_OVERRIDE_PREFIX)?;
+            Some((key.to_lowercase().replace("__", "."), value))
+        })
+        .collect();
+    generic.sort();
+    overrides.extend(generic);
+
+    overrides
+}
+
+// Sets `section.key` from a raw value, read as toml when possible and as a string otherwise.
+fn set_key(table: &mut toml::Table, key: &str, raw: &str, source: &str) -> Result<(), ConfigError> {
+    let value = match toml::from_str::<toml::Table>(&format!("value = {}", raw)) {
+        Ok(mut parsed) => parsed.remove("value").unwrap(),
+        Err(_) => toml::Value::String(raw.to_string()),
+    };
+
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or_default();
+    if last.is_empty() {
+        return Err(ConfigError::new(&format!("{}: empty key", source)));
+    }
+
+    let mut current = table;
+    for part in parts {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert(toml::Value::Table(toml::Table::new()));
+        current = match entry {
+            toml::Value::Table(t) => t,
+            _ => {
+                return Err(ConfigError::new(&format!(
+                    "{} {}: {} is not a section",
+                    source, key, part
+                )))
+            }
+        };
+    }
+    current.insert(last.to_string(), value);
+
+    Ok(())
+}
+
+// Keeps the configuration for the rest of the run. Only the first call has effect.
+pub fn set(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn overrides_and_validates() {
+        let mut table: toml::Table = toml::from_str("[watch]\ninterval_secs = 60\n").unwrap();
+        set_key(&mut table, "history.limit", "50", "--set").unwrap();
+        set_key(&mut table, "channels.include", "[\"general\"]", "--set").unwrap();
+        set_key(&mut table, "storage.dir", "/tmp/slack", "--set").unwrap();
+
+        let config = Config::deserialize(toml::Value::Table(table)).unwrap();
+        assert_eq!(config.watch.interval_secs, 60);
+        assert_eq!(config.watch.tracked_threads, 30);
+        assert_eq!(config.history.limit, 50);
+        assert_eq!(config.storage.dir, PathBuf::from("/tmp/slack"));
+        assert!(config.channels.selects("C07BSNU3GG1", "general"));
+        assert!(!config.channels.selects("C07BSNU3GG1", "random"));
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        invalid.watch.interval_secs = 1;
+        assert!(invalid
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .starts_with("watch.interval_secs:"));
+    }
+
+    #[test]
+    fn points_to_unknown_keys() {
+        let mut table = toml::Table::new();
+        set_key(&mut table, "watch.intervl_secs", "60", "--set").unwrap();
+
+        let error = Config::deserialize(toml::Value::Table(table)).unwrap_err();
+        assert!(error.to_string().contains("intervl_secs"));
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use super::ids_str::{TeamId, UserId};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuthTestResponse {
+    pub ok: bool,
+    pub url: Option<String>,
+    pub team: Option<String>,
+    pub user: Option<String>,
+    pub team_id: Option<TeamId>,
+    pub user_id: Option<UserId>,
+    pub bot_id: Option<String>,
+    pub error: Option<String>,
+}
+
+// The fields every slack web api response shares.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ApiStatus {
+    pub ok: bool,
+    pub error: Option<String>,
+    // Scope required by a `missing_scope` error
+    pub needed: Option<String>,
+    pub provided: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::ApiStatus;
+
+    #[test]
+    fn parses_missing_scope_status() {
+        let error_response = "{
+           \"ok\": false,
+           \"error\":  \"missing_scope\",
+           \"needed\": \"usergroups:read\",
+           \"provided\": \"identify,channels:history\",
+           \"warning\": \"superfluous_charset\"
+        }";
+        let status: ApiStatus = serde_json::from_str(error_response).unwrap();
+
+        assert!(!status.ok);
+        assert_eq!(status.needed, Some(String::from("usergroups:read")));
+    }
+}
@@ -1,8 +1,12 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+use super::ids_str::{ChannelId, TeamId, UserId};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChannelResponse {
     pub ok: bool,
+    #[serde(default, deserialize_with = "lenient_channels")]
     pub channels: Option<Vec<ConversationChannel>>,
     pub error: Option<String>,
     pub needed: Option<String>,
@@ -13,58 +17,191 @@ pub struct ChannelResponse {
 //  A channel-like conversations in a workspace
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ConversationChannel {
-    pub id: String,
+    pub id: ChannelId,
+    #[serde(default)]
+    pub created: usize,
+    #[serde(default)]
+    pub updated: usize,
+    #[serde(default)]
+    pub is_archived: bool,
+    #[serde(default)]
+    pub is_org_shared: bool,
+    // Missing on some Enterprise Grid conversations
+    #[serde(default)]
+    pub context_team_id: Option<TeamId>,
+    #[serde(flatten)]
+    pub kind: ConversationKind,
+}
+
+// What only some kinds of conversation carry, told apart by is_im and is_mpim.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ConversationKind {
+    Im(DirectMessage),
+    Mpim(GroupMessage),
+    // Public and private channels
+    Channel(ChannelData),
+}
+
+impl<'de> Deserialize<'de> for ConversationKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = Map::<String, Value>::deserialize(deserializer)?;
+        let flag = |name: &str| fields.get(name).and_then(Value::as_bool) == Some(true);
+        let (is_im, is_mpim) = (flag("is_im"), flag("is_mpim"));
+        let fields = Value::Object(fields);
+
+        let kind = if is_im {
+            serde_json::from_value(fields).map(ConversationKind::Im)
+        } else if is_mpim {
+            serde_json::from_value(fields).map(ConversationKind::Mpim)
+        } else {
+            serde_json::from_value(fields).map(ConversationKind::Channel)
+        };
+        kind.map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChannelData {
     pub name: String,
+    #[serde(default)]
+    pub name_normalized: String,
+    #[serde(default)]
     pub is_channel: bool,
+    #[serde(default)]
     pub is_group: bool,
-    pub is_im: bool,
-    pub is_mpim: bool,
+    #[serde(default)]
     pub is_private: bool,
-    pub created: usize,
-    pub is_archived: bool,
+    #[serde(default)]
     pub is_general: bool,
-    pub unlinked: usize,
-    pub name_normalized: String,
+    #[serde(default)]
+    pub is_member: bool,
+    #[serde(default)]
     pub is_shared: bool,
-    pub is_org_shared: bool,
+    #[serde(default)]
+    pub is_ext_shared: bool,
+    #[serde(default)]
     pub is_pending_ext_shared: bool,
+    #[serde(default)]
+    pub unlinked: usize,
+    #[serde(default)]
     pub pending_shared: Vec<String>,
-    pub context_team_id: String,
-    pub updated: usize,
+    #[serde(default)]
     pub parent_conversation: Option<String>,
-    pub creator: String,
-    pub is_ext_shared: bool,
+    #[serde(default)]
+    pub creator: Option<UserId>,
+    #[serde(default)]
     pub shared_team_ids: Option<Vec<String>>,
+    #[serde(default)]
     pub pending_connected_team_ids: Vec<String>,
-    pub is_member: bool,
-    pub topic: Topic,
-    pub purpose: Topic,
+    #[serde(default)]
+    pub topic: Option<Topic>,
+    #[serde(default)]
+    pub purpose: Option<Topic>,
+    #[serde(default)]
     pub previous_names: Vec<String>,
-    pub num_members: usize,
+    #[serde(default)]
+    pub num_members: Option<usize>,
     // properties: Map<String, Map<String, usize | String | Boolean>>
 }
 
+// A direct message, with no name nor membership, only the other user.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DirectMessage {
+    pub is_im: bool,
+    pub user: UserId,
+    #[serde(default)]
+    pub is_user_deleted: bool,
+}
+
+// A group direct message, named after its members ("mpdm-ana--bob-1").
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GroupMessage {
+    pub is_mpim: bool,
+    pub name: String,
+    #[serde(default)]
+    pub creator: Option<UserId>,
+    #[serde(default)]
+    pub topic: Option<Topic>,
+    #[serde(default)]
+    pub purpose: Option<Topic>,
+    #[serde(default)]
+    pub num_members: Option<usize>,
+}
+
 impl ConversationChannel {
     pub fn is_elegible(&self) -> bool {
-        return self.is_member == true && !self.is_archived;
+        if self.is_archived {
+            return false;
+        }
+
+        match &self.kind {
+            ConversationKind::Im(im) => !im.is_user_deleted,
+            // Only the group messages you are in are listed
+            ConversationKind::Mpim(_) => true,
+            ConversationKind::Channel(channel) => channel.is_member,
+        }
+    }
+
+    // Direct messages have none
+    pub fn name(&self) -> Option<&str> {
+        match &self.kind {
+            ConversationKind::Im(_) => None,
+            ConversationKind::Mpim(mpim) => Some(&mpim.name),
+            ConversationKind::Channel(channel) => Some(&channel.name),
+        }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Topic {
+    #[serde(default)]
     pub value: String,
+    #[serde(default)]
     pub creator: String,
+    #[serde(default)]
     pub last_set: usize,
 }
 
+// Decodes every conversation on its own, skipping the ones that fail.
+fn lenient_channels<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<ConversationChannel>>, D::Error> {
+    let values = Option::<Vec<Value>>::deserialize(deserializer)?;
+
+    Ok(values.map(|values| {
+        values
+            .into_iter()
+            .filter_map(|value| {
+                let id = value.get("id").cloned();
+                match serde_json::from_value(value) {
+                    Ok(channel) => Some(channel),
+                    Err(e) => {
+                        eprintln!(
+                            "\x1b[93mSkipping conversation {}: {}\x1b[0m",
+                            id.unwrap_or_default(),
+                            e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    }))
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PaginationMetadata {
+    // Missing when the metadata only carries warnings
+    #[serde(default)]
     pub next_cursor: String,
 }
 
 #[cfg(test)]
 mod test {
-    use crate::conversation::channels_str::{ChannelResponse, ConversationChannel};
+    use crate::conversation::channels_str::{
+        ChannelResponse, ConversationChannel, ConversationKind,
+    };
 
     #[test]
     fn sample_load() {
@@ -113,6 +250,115 @@ mod test {
         let channel: ConversationChannel = serde_json::from_str(&serialized).unwrap();
 
         assert_eq!(channel.id, "C07BSNU3GG1");
+        assert_eq!(channel.name(), Some("feature-navigation"));
+        assert!(!channel.is_elegible());
+    }
+
+    #[test]
+    fn private_channel_load() {
+        // Enterprise Grid leaves out the team, the creator and the members count
+        let serialized = "
+        {
+          \"id\": \"G07C4B2K0HU\",
+          \"name\": \"incidents\",
+          \"is_channel\": false,
+          \"is_group\": true,
+          \"is_im\": false,
+          \"is_mpim\": false,
+          \"is_private\": true,
+          \"created\": 1720428655,
+          \"is_archived\": false,
+          \"is_member\": true,
+          \"enterprise_id\": \"E0279E2GQPQ\",
+          \"topic\": {
+            \"value\": \"On call\"
+          }
+        }
+        ";
+        let channel: ConversationChannel = serde_json::from_str(serialized).unwrap();
+
+        assert_eq!(channel.name(), Some("incidents"));
+        assert!(channel.is_elegible());
+        match channel.kind {
+            ConversationKind::Channel(data) => {
+                assert!(data.is_private);
+                assert_eq!(data.topic.unwrap().value, "On call");
+            }
+            kind => panic!("not a channel: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn direct_message_load() {
+        let serialized = "
+        {
+          \"id\": \"D07C4B2K0HU\",
+          \"created\": 1720428655,
+          \"is_archived\": false,
+          \"is_im\": true,
+          \"is_org_shared\": false,
+          \"context_team_id\": \"T0279E2GQPQ\",
+          \"updated\": 1720428655907,
+          \"user\": \"U04853SN1AP\",
+          \"is_user_deleted\": false,
+          \"priority\": 0
+        }
+        ";
+        let channel: ConversationChannel = serde_json::from_str(serialized).unwrap();
+
+        assert_eq!(channel.name(), None);
+        assert!(channel.is_elegible());
+        match channel.kind {
+            ConversationKind::Im(im) => assert_eq!(im.user, "U04853SN1AP"),
+            kind => panic!("not a direct message: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn group_message_load() {
+        let serialized = "
+        {
+          \"id\": \"C07D1LK9Q3X\",
+          \"name\": \"mpdm-ana--bob--carla-1\",
+          \"is_channel\": false,
+          \"is_group\": false,
+          \"is_im\": false,
+          \"is_mpim\": true,
+          \"is_private\": true,
+          \"created\": 1720428655,
+          \"is_archived\": false,
+          \"context_team_id\": \"T0279E2GQPQ\",
+          \"updated\": 1720428655907,
+          \"creator\": \"U04853SN1AP\",
+          \"priority\": 0
+        }
+        ";
+        let channel: ConversationChannel = serde_json::from_str(serialized).unwrap();
+
+        assert!(matches!(channel.kind, ConversationKind::Mpim(_)));
+        assert_eq!(channel.name(), Some("mpdm-ana--bob--carla-1"));
+        assert!(channel.is_elegible());
+    }
+
+    #[test]
+    fn skips_the_conversations_that_fail() {
+        let response = "{
+           \"ok\": true,
+           \"channels\": [
+             {\"id\": \"D07C4B2K0HU\", \"is_im\": true, \"user\": \"U04853SN1AP\"},
+             {\"id\": \"D07C4B2K0HV\", \"is_im\": true, \"user\": 42},
+             {\"id\": \"C07BSNU3GG1\", \"name\": \"general\", \"is_member\": true}
+           ]
+        }";
+        let response: ChannelResponse = serde_json::from_str(response).unwrap();
+
+        let ids: Vec<String> = response
+            .channels
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id.to_string())
+            .collect();
+        assert_eq!(ids, vec!["D07C4B2K0HU", "C07BSNU3GG1"]);
     }
 
     #[test]
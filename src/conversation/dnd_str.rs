@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DndInfoResponse {
+    pub ok: bool,
+    pub error: Option<String>,
+    // The scheduled do not disturb of the user
+    pub dnd_enabled: Option<bool>,
+    pub next_dnd_start_ts: Option<i64>,
+    pub next_dnd_end_ts: Option<i64>,
+    // Snoozed by hand
+    pub snooze_enabled: Option<bool>,
+    pub snooze_endtime: Option<i64>,
+}
+
+impl DndInfoResponse {
+    pub fn is_active(&self, now: i64) -> bool {
+        let snoozed =
+            self.snooze_enabled.unwrap_or(false) && self.snooze_endtime.unwrap_or(0) > now;
+        let scheduled = self.dnd_enabled.unwrap_or(false)
+            && match (self.next_dnd_start_ts, self.next_dnd_end_ts) {
+                (Some(start), Some(end)) => start <= now && now < end,
+                _ => false,
+            };
+
+        snoozed || scheduled
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DndInfoResponse;
+
+    #[test]
+    fn tells_when_dnd_is_active() {
+        let info: DndInfoResponse = serde_json::from_str(
+            r#"{"ok": true, "dnd_enabled": true, "next_dnd_start_ts": 1000, "next_dnd_end_ts": 2000,
+                "snooze_enabled": true, "snooze_endtime": 500, "snooze_remaining": 100}"#,
+        )
+        .unwrap();
+
+        assert!(info.is_active(400));
+        assert!(!info.is_active(700));
+        assert!(info.is_active(1500));
+        assert!(!info.is_active(2000));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::conversation::errors_str::ArchiveError;
+use crate::conversation::ids_str::ChannelId;
+use crate::conversation::messages_str::MessageNormal;
+use crate::conversation::services::archive_db::Archive;
+
+use super::{channels_service::Channel, users::User};
+
+// Archiving must never stop the watcher, failures are only reported.
+fn with_archive<F>(action: &str, f: F)
+where
+    F: FnOnce(&mut Archive) -> Result<(), ArchiveError>,
+{
+    let mut archive = match Archive::from_config() {
+        Ok(Some(a)) => a,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Error opening the message archive. {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = f(&mut archive) {
+        eprintln!("Error with the archived {}. {}", action, e);
+    }
+}
+
+// Threads you are in with replies since `since`, empty without an archive.
+pub fn archived_threads(user_id: &str, since: u64) -> Vec<(ChannelId, String)> {
+    let mut threads = Vec::new();
+    with_archive("threads", |archive| {
+        threads = archive.involved_threads(user_id, since)?;
+        Ok(())
+    });
+
+    threads
+}
+
+pub fn archive_messages(channel_id: &str, messages: &[MessageNormal]) {
+    with_archive("messages", |archive| {
+        archive.save_messages(channel_id, messages)?;
+        Ok(())
+    });
+}
+
+pub fn archive_channels(channels: &[Channel]) {
+    with_archive("channels", |archive| {
+        for c in channels {
+            archive.save_channel(&c.channel_id, &c.name, c.should_skip)?;
+        }
+        Ok(())
+    });
+}
+
+pub fn archive_users(users: &[User]) {
+    with_archive("users", |archive| {
+        for u in users {
+            archive.save_user(u.id(), u.name(), u.should_follow)?;
+        }
+        Ok(())
+    });
+}
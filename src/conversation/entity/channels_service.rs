@@ -1,31 +1,54 @@
+use std::ops::DerefMut;
 use std::thread;
 
-use crate::conversation::channels_str::ConversationChannel;
-use crate::conversation::errors_str::SlackChannelError;
+use chrono::{Local, LocalResult, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use super::archive_service;
+use crate::config::{self, ConversationType};
+use crate::conversation::channels_str::{ConversationChannel, ConversationKind};
+use crate::conversation::errors_str::{FileSystemError, SlackChannelError};
+use crate::conversation::ids_str::{ChannelId, UserId};
 use crate::conversation::messages_str::MessageNormal;
 use crate::conversation::methods_aggregate::ChatHistoryOptions;
-use crate::conversation::services::chat_history::get_chat_reply;
+use crate::conversation::services::chat_history::{get_chat_reply, get_thread_replies};
 use crate::conversation::services::{
-    channels_cache_fs::{create_cache, read_cache, ChannelStorage},
+    channels_cache_fs::{cached_types, create_cache, read_cache, update_cache, ChannelStorage},
     chat_channels::get_conversation_channels,
     chat_history::get_chat_history,
+    users_cache_fs,
 };
 
+// Page size for conversations.replies
+const THREAD_PAGE: u32 = 200;
+// Page size for conversations.history over a time range
+const HISTORY_PAGE: u32 = 200;
+
 #[derive(Debug, Clone)]
 pub struct Channel {
     // Channel name with "-" instead of spaces
     pub name: String,
-    // Identifier. Starts with "C"
-    pub channel_id: String,
+    // Identifier. Starts with "C", "G" or "D" for direct messages
+    pub channel_id: ChannelId,
     // Should be included in further steps to fetch channel messages.
     pub should_skip: bool,
 }
 
 impl From<&ConversationChannel> for Channel {
     fn from(cc: &ConversationChannel) -> Self {
-        return Channel::new(cc.name.clone(), cc.id.clone(), false);
+        let name = match &cc.kind {
+            // Direct messages have no name, the other user stands for it
+            ConversationKind::Im(im) => format!("@{}", im.user),
+            _ => cc.name().unwrap_or_default().to_string(),
+        };
+        return Channel::new(name, cc.id.clone(), false);
     }
 }
+
+// Direct and group direct messages. Group ones are named "mpdm-<users>-1".
+pub fn is_direct(channel_id: &str, name: &str) -> bool {
+    channel_id.starts_with('D') || name.starts_with("mpdm-")
+}
 impl From<&ChannelStorage> for Channel {
     fn from(cs: &ChannelStorage) -> Self {
         return Channel::new(cs.name.clone(), cs.channel_id.clone(), cs.ignore);
@@ -33,7 +56,11 @@ impl From<&ChannelStorage> for Channel {
 }
 
 impl Channel {
-    fn new(name: String, channel_id: String, should_skip: bool) -> Channel {
+    pub fn is_direct(&self) -> bool {
+        is_direct(&self.channel_id, &self.name)
+    }
+
+    pub fn new(name: String, channel_id: ChannelId, should_skip: bool) -> Channel {
         return Channel {
             name,
             channel_id,
@@ -44,29 +71,28 @@ impl Channel {
     pub async fn load_slack_channels() -> Result<Vec<Channel>, SlackChannelError> {
         // Try load first the cache files.
         match read_cache() {
+            Ok(cached) if cached_types() == config::get().channels.types => {
+                let cached_channs: Vec<Channel> = cached.iter().map(|c| c.into()).collect();
+                archive_service::archive_channels(&cached_channs);
+                return Ok(cached_channs);
+            }
+            // Listed with other channels.types
             Ok(cached) => {
-                return Ok(cached.iter().map(|c| c.into()).collect());
+                eprintln!("\x1b[93mchannels.types changed, refreshing the channels cache\x1b[0m");
+                let refreshed = match Channel::refresh_slack_channels().await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        eprintln!("\x1b[93mError refreshing the channels cache {}\x1b[0m", e);
+                        cached
+                    }
+                };
+                return Ok(refreshed.iter().map(|c| c.into()).collect());
             }
             Err(_) => (),
         };
 
         // Load the channels
-        let channels = get_conversation_channels(None).await;
-        if let Err(cha) = channels {
-            return Err(SlackChannelError::new(&cha.to_string()));
-        }
-
-        let channel_details = channels.unwrap().channels;
-        if let None = channel_details {
-            return Ok(vec![]);
-        }
-        let channel_details = channel_details.unwrap();
-
-        let lack_channs: Vec<Channel> = channel_details
-            .iter()
-            .filter(|c| c.is_elegible())
-            .map(|f| f.into())
-            .collect();
+        let lack_channs = Channel::fetch_slack_channels().await?;
 
         // Store the cache
         let lack_channs_clone = lack_channs.clone();
@@ -81,14 +107,260 @@ impl Channel {
                 })
                 .collect();
             if let Err(fail_cached) = create_cache(&to_cache_channels) {
-                println!("Error Creating slack channels cache file.");
-                println!("{fail_cached:?}");
+                eprintln!("Error Creating slack channels cache file.");
+                eprintln!("{fail_cached:?}");
             }
         });
 
         return Ok(lack_channs);
     }
 
+    // Every page of elegible channels, skipping the cache. Types whose read scope is missing are left out.
+    pub async fn fetch_slack_channels() -> Result<Vec<Channel>, SlackChannelError> {
+        let mut types = config::get().channels.types.clone();
+        let mut lack_channs: Vec<Channel> = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let channels = get_conversation_channels(cursor.as_deref(), &types).await;
+            if let Err(cha) = channels {
+                return Err(SlackChannelError::new(&cha.to_string()));
+            }
+            let channels = channels.unwrap();
+            if !channels.ok {
+                let needed = channels.needed.unwrap_or_default();
+                let (missing, granted): (Vec<ConversationType>, Vec<ConversationType>) = types
+                    .iter()
+                    .partition(|t| needed.split(',').any(|n| n == t.read_scope()));
+                if channels.error.as_deref() == Some("missing_scope")
+                    && !missing.is_empty()
+                    && !granted.is_empty()
+                {
+                    for t in missing {
+                        eprintln!(
+                            "\x1b[93mLeaving out channels.types {}, the token lacks {}\x1b[0m",
+                            t.as_str(),
+                            t.read_scope()
+                        );
+                    }
+                    // Starts over with the types left
+                    types = granted;
+                    lack_channs.clear();
+                    cursor = None;
+                    continue;
+                }
+                return Err(SlackChannelError::new(
+                    &channels.error.unwrap_or("Unknown channels error".into()),
+                ));
+            }
+
+            lack_channs.extend(
+                channels
+                    .channels
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|c| c.is_elegible())
+                    .map(|f| f.into()),
+            );
+
+            cursor = channels
+                .response_metadata
+                .map(|m| m.next_cursor)
+                .filter(|c| !c.is_empty());
+            if cursor.is_none() {
+                break;
+            }
+        }
+        // Name direct messages after the user when the cache knows them
+        let users = users_cache_fs::read_cache().unwrap_or_default();
+        for chann in lack_channs.iter_mut() {
+            let user = chann.name.strip_prefix('@').unwrap_or_default();
+            if let Some(known) = users.iter().find(|u| !user.is_empty() && u.id() == user) {
+                chann.name = format!("@{}", known.name());
+            }
+        }
+        archive_service::archive_channels(&lack_channs);
+
+        Ok(lack_channs)
+    }
+
+    // Rewrites the cache with the current channels, keeping ignored and custom ones.
+    pub async fn refresh_slack_channels() -> Result<Vec<ChannelStorage>, SlackChannelError> {
+        let cached = read_cache().unwrap_or_default();
+        let fetched = Channel::fetch_slack_channels().await?;
+
+        let mut refreshed: Vec<ChannelStorage> = fetched
+            .iter()
+            .map(|c| ChannelStorage {
+                channel_id: c.channel_id.clone(),
+                name: c.name.clone(),
+                custom: false,
+                ignore: cached
+                    .iter()
+                    .any(|cs| cs.channel_id == c.channel_id && cs.ignore),
+            })
+            .collect();
+        for cs in cached {
+            if cs.custom && !refreshed.iter().any(|r| r.channel_id == cs.channel_id) {
+                refreshed.push(cs);
+            }
+        }
+
+        if let Err(e) = update_cache(&refreshed) {
+            return Err(SlackChannelError::new(&e.to_string()));
+        }
+
+        Ok(refreshed)
+    }
+
+    // Marks a cached channel, by id or name, to be skipped by the watcher.
+    pub fn set_ignored(channel: &str, ignore: bool) -> Result<Channel, FileSystemError> {
+        let mut cached = read_cache()?;
+        let found = cached
+            .iter_mut()
+            .find(|c| c.channel_id == channel || c.name == channel);
+        let updated: Channel = match found {
+            Some(c) => {
+                c.ignore = ignore;
+                (&*c).into()
+            }
+            None => {
+                return Err(FileSystemError::new(&format!(
+                    "Unknown channel {}",
+                    channel
+                )))
+            }
+        };
+
+        update_cache(&cached)?;
+        Ok(updated)
+    }
+
+    // Adds a channel the listing does not return, ie. one the token is not a member of.
+    pub fn add_custom(channel_id: ChannelId, name: &str) -> Result<Channel, FileSystemError> {
+        let mut cached = read_cache().unwrap_or_default();
+        cached.retain(|c| c.channel_id != channel_id);
+        let added = ChannelStorage {
+            channel_id,
+            name: name.into(),
+            custom: true,
+            ignore: false,
+        };
+        let channel: Channel = (&added).into();
+        cached.push(added);
+
+        update_cache(&cached)?;
+        Ok(channel)
+    }
+
+    // Latest messages of the channel, oldest first.
+    pub async fn load_history(&self, limit: u32) -> Result<Vec<Message>, SlackChannelError> {
+        let mut history_options = ChatHistoryOptions::default();
+        history_options.set_range(0, None);
+        history_options.set_limit(limit);
+        let chats = get_chat_history(&self.channel_id, Some(history_options)).await;
+        if let Err(err) = chats {
+            return Err(SlackChannelError::new(&err.to_string()));
+        }
+        let chats = chats.unwrap();
+        if !chats.ok {
+            return Err(SlackChannelError::new(
+                &chats.error.unwrap_or("Unknown history error".into()),
+            ));
+        }
+
+        let chat_details = chats.messages.unwrap_or_default();
+        archive_service::archive_messages(&self.channel_id, &chat_details);
+
+        let mut messages: Vec<Message> = chat_details
+            .iter()
+            .map(|f| {
+                let mut m: Message = f.into();
+                m.set_channel_id(&self.channel_id);
+                m
+            })
+            .collect();
+        messages.reverse();
+        Ok(messages)
+    }
+
+    // Every message between the timestamps, oldest first. Replies are not included.
+    pub async fn load_range(
+        &self,
+        since: u64,
+        until: Option<u64>,
+    ) -> Result<Vec<MessageNormal>, SlackChannelError> {
+        let mut history: Vec<MessageNormal> = Vec::new();
+        let mut history_options = ChatHistoryOptions::default();
+        history_options.set_range(since, until);
+        history_options.set_limit(HISTORY_PAGE);
+
+        loop {
+            let page = get_chat_history(&self.channel_id, Some(history_options.clone())).await;
+            if let Err(err) = page {
+                return Err(SlackChannelError::new(&err.to_string()));
+            }
+            let page = page.unwrap();
+            if !page.ok {
+                return Err(SlackChannelError::new(
+                    &page.error.unwrap_or("Unknown history error".into()),
+                ));
+            }
+
+            history.extend(page.messages.unwrap_or_default());
+            let next_cursor = page
+                .response_metadata
+                .map(|m| m.next_cursor)
+                .filter(|c| !c.is_empty());
+            if !page.has_more.unwrap_or(false) || next_cursor.is_none() {
+                break;
+            }
+            history_options.set_next_page(next_cursor);
+        }
+        // Slack sends the newest first
+        history.reverse();
+        archive_service::archive_messages(&self.channel_id, &history);
+
+        Ok(history)
+    }
+
+    // Every message of a thread, the parent message first.
+    pub async fn load_thread(
+        channel_id: &str,
+        thread_ts: &str,
+    ) -> Result<Vec<MessageNormal>, SlackChannelError> {
+        let mut thread: Vec<MessageNormal> = Vec::new();
+        let mut replies_options = ChatHistoryOptions::default();
+        replies_options.set_message_thread(channel_id, thread_ts);
+        replies_options.set_limit(THREAD_PAGE);
+
+        loop {
+            let page = get_thread_replies(&replies_options).await;
+            if let Err(err) = page {
+                return Err(SlackChannelError::new(&err.to_string()));
+            }
+            let page = page.unwrap();
+            if !page.ok {
+                return Err(SlackChannelError::new(
+                    &page.error.unwrap_or("Unknown replies error".into()),
+                ));
+            }
+
+            thread.extend(page.messages.unwrap_or_default());
+            let next_cursor = page
+                .response_metadata
+                .map(|m| m.next_cursor)
+                .filter(|c| !c.is_empty());
+            if !page.has_more.unwrap_or(false) || next_cursor.is_none() {
+                break;
+            }
+            replies_options.set_next_page(next_cursor);
+        }
+        archive_service::archive_messages(channel_id, &thread);
+
+        Ok(thread)
+    }
+
     pub async fn load_channel_messages(&self) -> Result<Vec<Message>, SlackChannelError> {
         let history_options = ChatHistoryOptions::default();
         let chats = get_chat_history(&self.channel_id, Some(history_options)).await;
@@ -101,8 +373,10 @@ impl Channel {
             return Ok(vec![]);
         }
 
+        let chat_details = chat_details.unwrap();
+        archive_service::archive_messages(&self.channel_id, &chat_details);
+
         let messages: Vec<Message> = chat_details
-            .unwrap()
             .iter()
             .filter(|f| f.is_elegible())
             .map(|f| f.into())
@@ -125,18 +399,13 @@ impl Channel {
         let chats = chats.unwrap();
         let chat_details = chats.messages;
         if let None = chat_details {
-            println!("No new messages");
             return Ok(None); // ((vec![]);
         }
 
-        println!(
-            "Last message for channelId: {} message ts: {} is at {}.",
-            channel_id,
-            message_id,
-            chats.oldest.unwrap_or("no_oldest".to_string()),
-        );
+        let chat_details = chat_details.unwrap();
+        archive_service::archive_messages(channel_id, &chat_details);
+
         let messages: Option<Message> = chat_details
-            .unwrap()
             .iter()
             .filter(|f| f.is_elegible())
             .map(|f| f.into())
@@ -146,30 +415,30 @@ impl Channel {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reply {
     // pub message_count: usize,
     pub latest_reply: usize,
     // Required for further conversation.replies query
     pub latest_ts: String,
     // User_id list who have sent reply messages
-    pub users: Vec<String>,
+    pub users: Vec<UserId>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     // Content text
     pub message: String,
     // Unique id of the channel, normally starts with C / D
-    pub channel_id: Option<String>,
+    pub channel_id: Option<ChannelId>,
     // ts in seconds when the message arrived
     pub received_at: usize,
     // Required for further conversation.replies query
     pub received_ts: String,
     // Reply information
     pub reply: Option<Reply>,
-    // user who sends the message
-    pub sender: String,
+    // user who sends the message, None for bots and integrations
+    pub sender: Option<UserId>,
 }
 
 impl From<&MessageNormal> for Message {
@@ -185,7 +454,7 @@ impl From<&MessageNormal> for Message {
 
         return Message::new(
             mn.text.clone(),
-            mn.user.as_ref().unwrap_or(&String::from("")).into(),
+            mn.user.clone(),
             Message::parse_ts(&mn.ts),
             mn.ts.clone(),
             reply,
@@ -196,7 +465,7 @@ impl From<&MessageNormal> for Message {
 impl Message {
     fn new(
         message: String,
-        sender: String,
+        sender: Option<UserId>,
         received_at: usize,
         received_ts: String,
         reply: Option<Reply>,
@@ -224,8 +493,16 @@ impl Message {
         return in_seconds;
     }
 
-    fn find_users_in_text(&self) -> Vec<String> {
-        let mut found_users: Vec<String> = Vec::new();
+    // Local date and time for a ts in seconds
+    pub fn display_time(seconds: usize) -> String {
+        match Local.timestamp_opt(seconds as i64, 0) {
+            LocalResult::Single(d) => d.format("%Y-%m-%d %H:%M").to_string(),
+            _ => seconds.to_string(),
+        }
+    }
+
+    fn find_users_in_text(&self) -> Vec<UserId> {
+        let mut found_users: Vec<UserId> = Vec::new();
 
         // 12 = 11(id)>
 
@@ -238,21 +515,20 @@ impl Message {
                 continue;
             }
             let char_start = start_i + 2;
-            let user_id = &self.message[char_start..=end_i];
-            found_users.push(user_id.into());
+            if let Ok(user_id) = UserId::parse(&self.message[char_start..=end_i]) {
+                found_users.push(user_id);
+            }
         }
 
         return found_users;
     }
 
-    pub fn users_list(&self) -> Vec<String> {
+    pub fn users_list(&self) -> Vec<UserId> {
         let mut users = self.find_users_in_text();
 
-        users.push(self.sender.clone());
+        users.extend(self.sender.clone());
         if let Some(rreply) = &self.reply {
-            for u in rreply.users.iter() {
-                users.push(u.clone());
-            }
+            users.extend(rreply.users.iter().cloned());
         }
 
         users.sort();
@@ -261,10 +537,16 @@ impl Message {
         users
     }
 
-    pub fn set_channel_id(&mut self, channel_id: &str) {
-        self.channel_id = Some(channel_id.into());
+    pub fn set_channel_id(&mut self, channel_id: &ChannelId) {
+        self.channel_id = Some(channel_id.clone());
     }
-    pub fn bubble_sort<const M: usize>(messages: &mut Box<[Option<Message>; M]>) {
+    // Takes the fixed size boxes as well as the watcher's runtime sized list
+    pub fn bubble_sort<S>(messages: &mut S)
+    where
+        S: DerefMut,
+        S::Target: AsMut<[Option<Message>]>,
+    {
+        let messages = messages.deref_mut().as_mut();
         for i in 0..messages.len() {
             for j in i..messages.len() - i - 1 {
                 // Expect no elements to the left
@@ -307,7 +589,7 @@ mod tests {
             received_at: 10000,
             received_ts: "10000.000".into(),
             reply: None,
-            sender: "U001".into(),
+            sender: Some("U0000000001".parse().unwrap()),
         })];
         let mut messages = Box::new(unsorted_3);
         Message::bubble_sort(&mut messages);
@@ -323,7 +605,7 @@ mod tests {
                 received_at: 1_000,
                 received_ts: "1000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
             Some(Message {
                 channel_id: None,
@@ -331,7 +613,7 @@ mod tests {
                 received_at: 10_000,
                 received_ts: "10000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
         ];
         let mut messages = Box::new(unsorted_3);
@@ -350,7 +632,7 @@ mod tests {
                 received_at: 100_000,
                 received_ts: "100000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
             Some(Message {
                 channel_id: None,
@@ -358,7 +640,7 @@ mod tests {
                 received_at: 1_000,
                 received_ts: "1000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
             Some(Message {
                 channel_id: None,
@@ -366,7 +648,7 @@ mod tests {
                 received_at: 10_000,
                 received_ts: "10000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
         ];
         let mut messages = Box::new(unsorted_3);
@@ -385,7 +667,7 @@ mod tests {
                 received_at: 100_000,
                 received_ts: "100000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
             Some(Message {
                 channel_id: None,
@@ -393,7 +675,7 @@ mod tests {
                 received_at: 1_000,
                 received_ts: "1000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
             None,
         ];
@@ -414,7 +696,7 @@ mod tests {
                 received_at: 1_000,
                 received_ts: "1000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
             None,
         ];
@@ -435,7 +717,7 @@ mod tests {
                 received_at: 1_000,
                 received_ts: "1000.000".into(),
                 reply: None,
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
             Some(Message {
                 channel_id: None,
@@ -447,7 +729,7 @@ mod tests {
                     latest_ts: "1000000".into(),
                     users: Vec::new(),
                 }),
-                sender: "U001".into(),
+                sender: Some("U0000000001".parse().unwrap()),
             }),
         ];
         let mut messages = Box::new(unsorted_3);
@@ -0,0 +1,639 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, DigestConfig, DigestFormat, DigestSchedule};
+use crate::conversation::errors_str::FileSystemError;
+use crate::conversation::ids_str::{ChannelId, UserId};
+use crate::conversation::messages_str::MessageNormal;
+use crate::conversation::services::{
+    state_fs::{read_state, write_state},
+    storage_path::storage_file,
+};
+
+use super::{
+    archive_service::archived_threads,
+    channels_service::{Channel, Message},
+    mrkdwn_service::MrkdwnRenderer,
+};
+
+static STATE_FILE: &str = "digest_state.json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelActivity {
+    pub channel_id: ChannelId,
+    pub channel_name: String,
+    pub messages: usize,
+}
+
+// A thread you started or replied to, with replies since the previous digest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadActivity {
+    pub channel_id: ChannelId,
+    pub channel_name: String,
+    pub thread_ts: String,
+    // Replies within the period
+    pub replies: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Mention {
+    pub channel_id: ChannelId,
+    pub channel_name: String,
+    pub ts: String,
+    pub sender: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Reactor {
+    pub user_id: UserId,
+    pub name: String,
+    pub reactions: usize,
+}
+
+// What a digest is made of.
+#[derive(Debug, Clone, Default)]
+pub struct Activity {
+    // Messages of every channel in the period
+    pub channels: Vec<(Channel, Vec<MessageNormal>)>,
+    // Threads you may be in, the parent message first
+    pub threads: Vec<(Channel, Vec<MessageNormal>)>,
+}
+
+// Activity of the watched channels between two digests, or while the watcher was off.
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub title: String,
+    // Seconds since epoch
+    pub since: u64,
+    pub until: u64,
+    // Busiest first
+    pub channels: Vec<ChannelActivity>,
+    pub threads: Vec<ThreadActivity>,
+    pub mentions: Vec<Mention>,
+    // Sent to you in direct conversations
+    pub direct_messages: Vec<Mention>,
+    pub reactors: Vec<Reactor>,
+    // Rendered in digest.format
+    pub text: String,
+}
+
+impl Digest {
+    // Fetches the history of every channel in the period, and the threads you are in that may have new replies:
+    // parents in that history, the tracked messages and the archive's threads. Channels failing to load are left out.
+    pub async fn load(
+        heading: &str,
+        channels: &[Channel],
+        tracked: &[Message],
+        since: u64,
+        until: u64,
+        me: Option<&UserId>,
+    ) -> Digest {
+        let mut activity = Activity::default();
+        for channel in channels.iter().filter(|c| !c.should_skip) {
+            match channel.load_range(since, Some(until)).await {
+                Ok(messages) => activity.channels.push((channel.clone(), messages)),
+                Err(e) => eprintln!(
+                    "\x1b[93mError loading #{} for the digest {}\x1b[0m",
+                    channel.name, e
+                ),
+            }
+        }
+
+        if let Some(me) = me {
+            for (channel_id, thread_ts) in thread_candidates(&activity.channels, tracked, since, me)
+            {
+                let Some(channel) = channels
+                    .iter()
+                    .find(|c| !c.should_skip && c.channel_id == channel_id)
+                else {
+                    continue;
+                };
+                match Channel::load_thread(&channel_id, &thread_ts).await {
+                    Ok(thread) => activity.threads.push((channel.clone(), thread)),
+                    Err(e) => eprintln!(
+                        "\x1b[93mError loading a thread of #{} for the digest {}\x1b[0m",
+                        channel.name, e
+                    ),
+                }
+            }
+        }
+
+        Digest::build(
+            heading,
+            since,
+            until,
+            &activity,
+            me,
+            &MrkdwnRenderer::from_caches(),
+            &config::get().digest,
+        )
+    }
+
+    pub fn build(
+        heading: &str,
+        since: u64,
+        until: u64,
+        activity: &Activity,
+        me: Option<&UserId>,
+        names: &MrkdwnRenderer,
+        config: &DigestConfig,
+    ) -> Digest {
+        let mut channels = Vec::new();
+        let mut mentions = Vec::new();
+        let mut direct_messages = Vec::new();
+        let mut reactions: HashMap<&UserId, usize> = HashMap::new();
+
+        for (channel, messages) in activity.channels.iter() {
+            let count = messages.iter().filter(|m| m.user.is_some()).count();
+            if count > 0 {
+                channels.push(ChannelActivity {
+                    channel_id: channel.channel_id.clone(),
+                    channel_name: channel.name.clone(),
+                    messages: count,
+                });
+            }
+
+            for message in messages {
+                for reaction in message.reactions.iter().flatten() {
+                    for user in reaction.users.iter() {
+                        *reactions.entry(user).or_default() += 1;
+                    }
+                }
+
+                let me = match me {
+                    Some(me) => me,
+                    None => continue,
+                };
+                let sender = message.user.as_ref();
+                let mention = || Mention {
+                    channel_id: channel.channel_id.clone(),
+                    channel_name: channel.name.clone(),
+                    ts: message.ts.clone(),
+                    sender: names.user_name(sender.map(|s| s.as_str()).unwrap_or_default()),
+                    text: names.to_plain(&message.text),
+                };
+                if sender.is_some() && sender != Some(me) {
+                    if channel.is_direct() {
+                        direct_messages.push(mention());
+                    } else if message.text.contains(&format!("<@{}>", me)) {
+                        mentions.push(mention());
+                    }
+                }
+            }
+        }
+
+        let in_period = |ts: &str| (since..until).contains(&(Message::parse_ts(ts) as u64));
+        let threads: Vec<ThreadActivity> = activity
+            .threads
+            .iter()
+            .filter_map(|(channel, thread)| {
+                let me = me?;
+                let parent = thread.first()?;
+                let involved = thread.iter().any(|m| m.user.as_ref() == Some(me))
+                    || parent.reply_users.iter().flatten().any(|u| u == me);
+                let replies = thread
+                    .iter()
+                    .filter(|m| m.ts != parent.ts && in_period(&m.ts))
+                    .count();
+                if !involved || replies == 0 {
+                    return None;
+                }
+
+                Some(ThreadActivity {
+                    channel_id: channel.channel_id.clone(),
+                    channel_name: channel.name.clone(),
+                    thread_ts: parent.ts.clone(),
+                    replies,
+                    text: names.to_plain(&parent.text),
+                })
+            })
+            .collect();
+
+        channels.sort_by_key(|c| std::cmp::Reverse(c.messages));
+        let mut reactors: Vec<Reactor> = reactions
+            .into_iter()
+            .map(|(user_id, reactions)| Reactor {
+                user_id: user_id.clone(),
+                name: names.user_name(user_id),
+                reactions,
+            })
+            .collect();
+        // Ties by name, so the order does not depend on the map
+        reactors.sort_by(|a, b| b.reactions.cmp(&a.reactions).then(a.name.cmp(&b.name)));
+        reactors.truncate(config.top_reactors);
+
+        let mut digest = Digest {
+            title: format!(
+                "{}, {} to {}",
+                heading,
+                Message::display_time(since as usize),
+                Message::display_time(until as usize)
+            ),
+            since,
+            until,
+            channels,
+            threads,
+            mentions,
+            direct_messages,
+            reactors,
+            text: String::new(),
+        };
+        digest.text = match config.format {
+            DigestFormat::Text => digest.to_text(),
+            DigestFormat::Markdown => digest.to_markdown(),
+        };
+
+        digest
+    }
+
+    // Appends the text to the file, relative to the storage folder.
+    pub fn append_to(&self, file: &str) -> Result<(), FileSystemError> {
+        let path = storage_file(file);
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| writeln!(f, "{}", self.text));
+
+        match written {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                eprintln!("{:?}", e);
+                Err(FileSystemError::new(&format!(
+                    "Failed to write the digest to {}",
+                    path.display()
+                )))
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+            && self.threads.is_empty()
+            && self.mentions.is_empty()
+            && self.direct_messages.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        let snippet = |text: &str| -> String { text.replace('\n', " ").chars().take(80).collect() };
+        let mut text = format!("{}\n", self.title);
+        if self.is_empty() {
+            text.push_str("No new messages\n");
+        }
+
+        if !self.channels.is_empty() {
+            text.push_str("\nMessages\n");
+            for c in self.channels.iter() {
+                text.push_str(&format!("  #{} {}\n", c.channel_name, c.messages));
+            }
+        }
+        if !self.threads.is_empty() {
+            text.push_str("\nYour threads with new replies\n");
+            for t in self.threads.iter() {
+                text.push_str(&format!(
+                    "  #{} {} replies: {}\n",
+                    t.channel_name,
+                    t.replies,
+                    snippet(&t.text)
+                ));
+            }
+        }
+        if !self.mentions.is_empty() {
+            text.push_str("\nMentions\n");
+            for m in self.mentions.iter() {
+                text.push_str(&format!(
+                    "  #{} by {}: {}\n",
+                    m.channel_name,
+                    m.sender,
+                    snippet(&m.text)
+                ));
+            }
+        }
+        if !self.direct_messages.is_empty() {
+            text.push_str("\nDirect messages\n");
+            for m in self.direct_messages.iter() {
+                text.push_str(&format!("  {}: {}\n", m.sender, snippet(&m.text)));
+            }
+        }
+        if !self.reactors.is_empty() {
+            text.push_str("\nTop reactors\n");
+            for r in self.reactors.iter() {
+                text.push_str(&format!("  {} {}\n", r.name, r.reactions));
+            }
+        }
+
+        text
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let quote = |text: &str| text.replace('\n', " ");
+        let mut markdown = format!("## {}\n", self.title);
+        if self.is_empty() {
+            markdown.push_str("\nNo new messages\n");
+        }
+
+        if !self.channels.is_empty() {
+            markdown.push_str("\n### Messages\n\n");
+            for c in self.channels.iter() {
+                markdown.push_str(&format!("- **#{}** {}\n", c.channel_name, c.messages));
+            }
+        }
+        if !self.threads.is_empty() {
+            markdown.push_str("\n### Your threads with new replies\n\n");
+            for t in self.threads.iter() {
+                markdown.push_str(&format!(
+                    "- **#{}** {} replies: {}\n",
+                    t.channel_name,
+                    t.replies,
+                    quote(&t.text)
+                ));
+            }
+        }
+        if !self.mentions.is_empty() {
+            markdown.push_str("\n### Mentions\n\n");
+            for m in self.mentions.iter() {
+                markdown.push_str(&format!(
+                    "- **#{}** _{}_: {}\n",
+                    m.channel_name,
+                    m.sender,
+                    quote(&m.text)
+                ));
+            }
+        }
+        if !self.direct_messages.is_empty() {
+            markdown.push_str("\n### Direct messages\n\n");
+            for m in self.direct_messages.iter() {
+                markdown.push_str(&format!("- _{}_: {}\n", m.sender, quote(&m.text)));
+            }
+        }
+        if !self.reactors.is_empty() {
+            markdown.push_str("\n### Top reactors\n\n");
+            for r in self.reactors.iter() {
+                markdown.push_str(&format!("- {} {}\n", r.name, r.reactions));
+            }
+        }
+
+        markdown
+    }
+}
+
+// Channel id and ts of the threads you started or replied to with a reply since `since`, each once.
+fn thread_candidates(
+    activity: &[(Channel, Vec<MessageNormal>)],
+    tracked: &[Message],
+    since: u64,
+    me: &UserId,
+) -> Vec<(ChannelId, String)> {
+    let replied_since = |latest_reply: &str| Message::parse_ts(latest_reply) as u64 >= since;
+    let mut candidates: Vec<(ChannelId, String)> = Vec::new();
+    for (channel, messages) in activity {
+        for message in messages {
+            let involved = message.user.as_ref() == Some(me)
+                || message.reply_users.iter().flatten().any(|u| u == me);
+            if involved && message.latest_reply.as_deref().is_some_and(replied_since) {
+                candidates.push((channel.channel_id.clone(), message.ts.clone()));
+            }
+        }
+    }
+    for message in tracked {
+        let (Some(channel_id), Some(reply)) = (&message.channel_id, &message.reply) else {
+            continue;
+        };
+        let involved = message.sender.as_ref() == Some(me) || reply.users.iter().any(|u| u == me);
+        if involved && replied_since(&reply.latest_ts) {
+            candidates.push((channel_id.clone(), message.received_ts.clone()));
+        }
+    }
+    candidates.extend(archived_threads(me, since));
+
+    let mut seen = Vec::new();
+    candidates.retain(|c| {
+        let first = !seen.contains(c);
+        seen.push(c.clone());
+        first
+    });
+    candidates
+}
+
+// When the previous digest was sent, kept between runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestState {
+    // Seconds since epoch, 0 before the first digest
+    pub last_at: u64,
+}
+
+impl DigestState {
+    pub fn load() -> Result<DigestState, FileSystemError> {
+        read_state(STATE_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), FileSystemError> {
+        write_state(STATE_FILE, self)
+    }
+}
+
+// When the digest after the one sent at `last` is due, None when they are off.
+pub fn next_digest(config: &DigestConfig, last: DateTime<Local>) -> Option<DateTime<Local>> {
+    match config.schedule {
+        DigestSchedule::Off => None,
+        DigestSchedule::Hourly => Some(last + Duration::hours(1)),
+        DigestSchedule::Daily => {
+            let at = NaiveTime::parse_from_str(&config.daily_at, "%H:%M").ok()?;
+            let mut day = last.date_naive();
+            // The first daily_at strictly after last
+            loop {
+                if let Some(next) = Local.from_local_datetime(&day.and_time(at)).earliest() {
+                    if next > last {
+                        return Some(next);
+                    }
+                }
+                day = day.succ_opt()?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conversation::entity::users::User;
+
+    fn message(user: &str, ts: &str, extra: &str) -> MessageNormal {
+        serde_json::from_str(&format!(
+            "{{\"user\": \"{}\", \"type\": \"message\", \"ts\": \"{}\", \"text\": \"hello\"{}}}",
+            user, ts, extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn summarises_the_activity() {
+        let general = Channel::new("general".into(), "C0000000001".parse().unwrap(), false);
+        let random = Channel::new("random".into(), "C0000000002".parse().unwrap(), false);
+        let names = MrkdwnRenderer::new(
+            &[
+                User::new("U0000000ME1".parse().unwrap(), "me", false),
+                User::new("U04853SN1AP".parse().unwrap(), "alice", true),
+            ],
+            &[general.clone(), random.clone()],
+        );
+        let thread_parent = message(
+            "U0000000002",
+            "1720428700.000200",
+            r#", "reply_count": 3, "latest_reply": "1720429000.000200", "reply_users": ["U0000000ME1"],
+               "reactions": [{"name": "eyes", "users": ["U04853SN1AP", "U0000000002"], "count": 2}]"#,
+        );
+        // Started before the period, only its last reply is new
+        let old_parent = message(
+            "U0000000ME1",
+            "1720420000.000200",
+            r#", "reply_count": 2, "latest_reply": "1720430000.000200", "reply_users": ["U04853SN1AP"]"#,
+        );
+        let threads = vec![
+            (
+                general.clone(),
+                vec![
+                    thread_parent.clone(),
+                    message("U0000000ME1", "1720428800.000200", ""),
+                    message("U04853SN1AP", "1720429000.000200", ""),
+                ],
+            ),
+            (
+                random.clone(),
+                vec![
+                    old_parent,
+                    message("U04853SN1AP", "1720421000.000200", ""),
+                    message("U04853SN1AP", "1720430000.000200", ""),
+                ],
+            ),
+            // Not yours
+            (
+                random.clone(),
+                vec![
+                    message("U04853SN1AP", "1720428000.000200", ""),
+                    message("U0000000002", "1720430000.000200", ""),
+                ],
+            ),
+        ];
+        let channels = vec![
+            (
+                general,
+                vec![
+                    message("U04853SN1AP", "1720428655.000200", ""),
+                    thread_parent,
+                ],
+            ),
+            (
+                random,
+                vec![serde_json::from_str(
+                    r#"{"user": "U04853SN1AP", "type": "message", "ts": "1720428800.000200", "text": "ping <@U0000000ME1>",
+                        "reactions": [{"name": "+1", "users": ["U04853SN1AP"], "count": 1}]}"#,
+                )
+                .unwrap()],
+            ),
+            (
+                Channel::new("alice".into(), "D0000000001".parse().unwrap(), false),
+                vec![message("U04853SN1AP", "1720428900.000200", "")],
+            ),
+        ];
+        let config = DigestConfig {
+            top_reactors: 1,
+            ..DigestConfig::default()
+        };
+        let me: UserId = "U0000000ME1".parse().unwrap();
+
+        // Only thread replies in the period still make a digest
+        let quiet = Digest::build(
+            "Slack digest",
+            1720428000,
+            1720432000,
+            &Activity {
+                channels: Vec::new(),
+                threads: threads.clone(),
+            },
+            Some(&me),
+            &names,
+            &config,
+        );
+        assert!(!quiet.is_empty());
+        assert!(quiet.text.contains("\nYour threads with new replies\n"));
+        assert!(!quiet.text.contains("No new messages"));
+
+        let activity = Activity { channels, threads };
+
+        let digest = Digest::build(
+            "Slack digest",
+            1720428000,
+            1720432000,
+            &activity,
+            Some(&me),
+            &names,
+            &config,
+        );
+
+        let counts: Vec<(&str, usize)> = digest
+            .channels
+            .iter()
+            .map(|c| (c.channel_name.as_str(), c.messages))
+            .collect();
+        assert_eq!(counts, vec![("general", 2), ("random", 1), ("alice", 1)]);
+        let threads: Vec<(&str, usize)> = digest
+            .threads
+            .iter()
+            .map(|t| (t.thread_ts.as_str(), t.replies))
+            .collect();
+        assert_eq!(
+            threads,
+            vec![("1720428700.000200", 2), ("1720420000.000200", 1)]
+        );
+        assert_eq!(digest.mentions.len(), 1);
+        assert_eq!(digest.mentions[0].text, "ping @me");
+        assert_eq!(digest.direct_messages.len(), 1);
+        assert!(digest.title.starts_with("Slack digest, 2024-07-08"));
+        assert_eq!(digest.reactors.len(), 1);
+        assert_eq!(
+            (
+                digest.reactors[0].name.as_str(),
+                digest.reactors[0].reactions
+            ),
+            ("alice", 2)
+        );
+        assert!(digest
+            .text
+            .contains("\nMentions\n  #random by alice: ping @me\n"));
+        assert!(digest
+            .to_markdown()
+            .contains("### Top reactors\n\n- alice 2\n"));
+    }
+
+    #[test]
+    fn schedules_hourly_and_daily() {
+        let last = Local.with_ymd_and_hms(2024, 7, 8, 10, 30, 0).unwrap();
+        let mut config = DigestConfig::default();
+        assert_eq!(next_digest(&config, last), None);
+
+        config.schedule = DigestSchedule::Hourly;
+        assert_eq!(
+            next_digest(&config, last),
+            Some(Local.with_ymd_and_hms(2024, 7, 8, 11, 30, 0).unwrap())
+        );
+
+        config.schedule = DigestSchedule::Daily;
+        config.daily_at = "09:00".into();
+        assert_eq!(
+            next_digest(&config, last),
+            Some(Local.with_ymd_and_hms(2024, 7, 9, 9, 0, 0).unwrap())
+        );
+        config.daily_at = "18:00".into();
+        assert_eq!(
+            next_digest(&config, last),
+            Some(Local.with_ymd_and_hms(2024, 7, 8, 18, 0, 0).unwrap())
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::conversation::errors_str::FileSystemError;
+use crate::conversation::ids_str::UserId;
+use crate::conversation::services::state_fs::{read_state, write_state};
+use crate::conversation::services::{users_cache_fs, users_list::get_user_info};
+
+use super::users::User;
+
+static FILE_NAME: &str = "users_directory.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DirectoryState {
+    users: Vec<User>,
+}
+
+// Who is behind every user id: the users cache with the profile details, then users.info.
+#[derive(Debug, Default)]
+pub struct UserDirectory {
+    users: HashMap<UserId, User>,
+    // Ids users.info answered user_not_found for, not asked again
+    unknown: HashSet<UserId>,
+}
+
+impl UserDirectory {
+    pub fn new(users: Vec<User>) -> UserDirectory {
+        UserDirectory {
+            users: users.into_iter().map(|u| (u.id().clone(), u)).collect(),
+            unknown: HashSet::new(),
+        }
+    }
+
+    // The profiles file, with who is followed taken from users_cache.txt.
+    pub fn load() -> UserDirectory {
+        let state: DirectoryState = match read_state(FILE_NAME) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("\x1b[93m{}, starting a new one\x1b[0m", e);
+                DirectoryState::default()
+            }
+        };
+        let mut directory = UserDirectory::new(state.users);
+        for cached in users_cache_fs::read_cache().unwrap_or_default() {
+            match directory.users.get_mut(cached.id()) {
+                Some(u) => u.should_follow = cached.should_follow,
+                None => directory.insert(cached),
+            }
+        }
+
+        directory
+    }
+
+    pub fn save(&self) -> Result<(), FileSystemError> {
+        let mut users: Vec<User> = self.users.values().cloned().collect();
+        users.sort_by(|a, b| a.id().cmp(b.id()));
+        write_state(FILE_NAME, &DirectoryState { users })
+    }
+
+    pub fn insert(&mut self, user: User) {
+        self.users.insert(user.id().clone(), user);
+    }
+
+    pub fn users(&self) -> Vec<&User> {
+        self.users.values().collect()
+    }
+
+    pub fn get(&self, user_id: &UserId) -> Option<&User> {
+        self.users.get(user_id)
+    }
+
+    // Asks users.info for the ids missing from the directory, saving what it learns.
+    // Stops at the first failure other than user_not_found, the rest is asked again next time.
+    pub async fn lookup(&mut self, user_ids: &[&UserId]) {
+        let mut learnt = false;
+        for user_id in user_ids.iter().map(|u| (*u).clone()) {
+            if self.users.contains_key(&user_id) || self.unknown.contains(&user_id) {
+                continue;
+            }
+            match get_user_info(&user_id).await {
+                Ok(info) if info.ok && info.user.is_some() => {
+                    self.insert(User::from(&info.user.unwrap()));
+                    learnt = true;
+                }
+                Ok(info) if info.error.as_deref() == Some("user_not_found") => {
+                    self.unknown.insert(user_id);
+                }
+                Ok(info) => {
+                    eprintln!(
+                        "\x1b[93musers.info {} failed. {}\x1b[0m",
+                        user_id,
+                        info.error.unwrap_or_default()
+                    );
+                    break;
+                }
+                Err(e) => {
+                    eprintln!("\x1b[93musers.info {} failed. {}\x1b[0m", user_id, e);
+                    break;
+                }
+            }
+        }
+
+        if learnt {
+            if let Err(e) = self.save() {
+                eprintln!("\x1b[93m{}\x1b[0m", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conversation::users_str::SlackUser;
+
+    #[tokio::test]
+    async fn resolves_known_users_without_asking() {
+        let member: SlackUser = serde_json::from_str(
+            r#"{"id": "U04853SN1AP", "name": "ana", "real_name": "Ana Lopez", "tz": "Europe/Madrid",
+                "profile": {"display_name": "", "image_72": "https://avatars.slack-edge.com/ana_72.png"}}"#,
+        )
+        .unwrap();
+        let mut directory = UserDirectory::new(vec![User::from(&member)]);
+
+        let ana_id: UserId = "U04853SN1AP".parse().unwrap();
+        directory.lookup(&[&ana_id]).await;
+        let ana = directory.get(&ana_id).unwrap();
+        assert_eq!(ana.name(), "Ana Lopez");
+        assert_eq!(ana.tz.as_deref(), Some("Europe/Madrid"));
+        assert!(ana.avatar.is_some());
+
+        assert!(directory.get(&"U0000000002".parse().unwrap()).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::config::{self, Config, ConversationType};
+use crate::conversation::methods_aggregate::METHOD;
+use crate::conversation::services::slack_probe::probe_method;
+
+// Something the tool does and the scopes the token needs for it.
+pub struct Feature {
+    pub name: &'static str,
+    pub scopes: &'static [&'static str],
+    probe: fn() -> METHOD,
+    query: &'static str,
+    // Whether the configuration makes use of it
+    pub enabled: fn(&Config) -> bool,
+}
+
+pub static FEATURES: &[Feature] = &[
+    Feature {
+        name: "Public channels",
+        scopes: &["channels:read", "channels:history"],
+        probe: || METHOD::Channels,
+        query: "types=public_channel&limit=1",
+        enabled: |c| c.channels.types.contains(&ConversationType::PublicChannel),
+    },
+    Feature {
+        name: "Private channels",
+        scopes: &["groups:read", "groups:history"],
+        probe: || METHOD::Channels,
+        query: "types=private_channel&limit=1",
+        enabled: |c| c.channels.types.contains(&ConversationType::PrivateChannel),
+    },
+    Feature {
+        name: "Direct messages",
+        scopes: &["im:read", "im:history"],
+        probe: || METHOD::Channels,
+        query: "types=im&limit=1",
+        enabled: |c| c.channels.types.contains(&ConversationType::Im),
+    },
+    Feature {
+        name: "Group direct messages",
+        scopes: &["mpim:read", "mpim:history"],
+        probe: || METHOD::Channels,
+        query: "types=mpim&limit=1",
+        enabled: |c| c.channels.types.contains(&ConversationType::Mpim),
+    },
+    Feature {
+        name: "Users",
+        scopes: &["users:read"],
+        probe: || METHOD::Users,
+        query: "limit=1",
+        enabled: |_| true,
+    },
+    Feature {
+        name: "User groups",
+        scopes: &["usergroups:read"],
+        probe: || METHOD::UserGroups,
+        query: "",
+        enabled: |_| false,
+    },
+    Feature {
+        name: "Do not disturb (quiet_hours.honour_slack_dnd)",
+        scopes: &["dnd:read"],
+        probe: || METHOD::DndInfo,
+        query: "",
+        enabled: |c| c.quiet_hours.honour_slack_dnd,
+    },
+];
+
+#[derive(Debug, Clone)]
+pub struct FeatureCheck {
+    pub feature: &'static str,
+    // Used by the current configuration
+    pub enabled: bool,
+    pub missing_scopes: Vec<String>,
+    // Error returned by the probe call
+    pub error: Option<String>,
+}
+
+impl FeatureCheck {
+    pub fn is_ok(&self) -> bool {
+        self.missing_scopes.is_empty() && self.error.is_none()
+    }
+}
+
+// Required scopes not in the granted list. Nothing is missing when the grants are unknown.
+pub fn missing_scopes(required: &[&str], granted: &[String]) -> Vec<String> {
+    if granted.is_empty() {
+        return Vec::new();
+    }
+
+    required
+        .iter()
+        .filter(|r| !granted.iter().any(|g| g == *r))
+        .map(|r| r.to_string())
+        .collect()
+}
+
+// Probes every feature and compares its scopes with the granted ones.
+pub async fn check_features(granted: &[String]) -> Vec<FeatureCheck> {
+    let mut checks = Vec::new();
+
+    for feature in FEATURES {
+        let mut check = FeatureCheck {
+            feature: feature.name,
+            enabled: (feature.enabled)(config::get()),
+            missing_scopes: missing_scopes(feature.scopes, granted),
+            error: None,
+        };
+
+        match probe_method((feature.probe)(), feature.query).await {
+            Ok(status) if status.ok => (),
+            Ok(status) => {
+                if let Some(needed) = status.needed {
+                    for scope in needed.split(',') {
+                        if !check.missing_scopes.iter().any(|m| m == scope) {
+                            check.missing_scopes.push(scope.to_string());
+                        }
+                    }
+                }
+                check.error = status.error;
+            }
+            Err(e) => check.error = Some(e.to_string()),
+        }
+        checks.push(check);
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod test {
+    use super::missing_scopes;
+
+    #[test]
+    fn finds_missing_scopes() {
+        let granted: Vec<String> = vec!["channels:read".into(), "im:read".into()];
+
+        assert_eq!(
+            missing_scopes(&["channels:read", "channels:history"], &granted),
+            vec![String::from("channels:history")]
+        );
+        assert!(missing_scopes(&["im:read"], &granted).is_empty());
+        assert!(missing_scopes(&["users:read"], &[]).is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+
+use crate::config::{self, EscalationConfig};
+
+use super::notification::NotifyEvent;
+use super::pending_service::PendingEvents;
+
+// A pending event that reached a new step, and who to tell about it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Escalation {
+    #[serde(flatten)]
+    pub event: NotifyEvent,
+    // Notifier types of the step, from the priority it had when notified
+    pub notifiers: Vec<String>,
+}
+
+// Makes unanswered events louder the longer they stay pending.
+#[derive(Debug, Clone, Default)]
+pub struct Escalations {
+    steps: EscalationConfig,
+}
+
+impl Escalations {
+    pub fn new(steps: EscalationConfig) -> Escalations {
+        Escalations { steps }
+    }
+
+    pub fn from_config() -> Escalations {
+        Escalations::new(config::get().escalation.clone())
+    }
+
+    // Pending events that reached a new step, with their escalation raised. Acknowledged ones are gone already.
+    // Pending is left as is, the step counts once it is sent.
+    pub fn due(&self, pending: &PendingEvents, now: u64) -> Vec<Escalation> {
+        let mut escalated = Vec::new();
+        for p in pending.events() {
+            let steps = self.steps.steps(p.event.priority);
+            let waited = now.saturating_sub(p.notified_at);
+            // Every step passed, several at once when the watcher was stopped
+            let reached = steps.iter().take_while(|s| waited >= s.after_secs).count();
+            if reached > p.event.escalation {
+                let mut event = p.event.clone();
+                event.escalation = reached;
+                escalated.push(Escalation {
+                    event,
+                    notifiers: steps[reached - 1].notifiers.clone(),
+                });
+            }
+        }
+
+        escalated
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{EscalationStep, QuietAction, QuietHoursConfig};
+    use crate::conversation::entity::notification::Priority;
+    use crate::conversation::entity::quiet_hours_service::QuietHours;
+
+    fn step(after_secs: u64, notifiers: &[&str]) -> EscalationStep {
+        EscalationStep {
+            after_secs,
+            notifiers: notifiers.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn escalates_step_by_step() {
+        let escalations = Escalations::new(EscalationConfig {
+            urgent: vec![
+                step(300, &[]),
+                step(900, &["command"]),
+                step(1800, &["webhook"]),
+            ],
+            ..EscalationConfig::default()
+        });
+        let mut pending = PendingEvents::default();
+        pending.add(
+            &[
+                NotifyEvent::sample("1720428655.000200", Priority::Urgent),
+                NotifyEvent::sample("1720428700.000200", Priority::Normal),
+            ],
+            1000,
+        );
+
+        assert!(escalations.due(&pending, 1299).is_empty());
+        let escalated = escalations.due(&pending, 1300);
+        assert_eq!(escalated.len(), 1);
+        assert_eq!(escalated[0].event.escalation, 1);
+        assert!(escalated[0].notifiers.is_empty());
+        // Due until sent
+        assert_eq!(escalations.due(&pending, 1400).len(), 1);
+        pending.mark_escalated(&escalated);
+        // Once per step
+        assert!(escalations.due(&pending, 1400).is_empty());
+
+        let escalated = escalations.due(&pending, 5000);
+        assert_eq!(escalated[0].event.escalation, 3);
+        assert_eq!(escalated[0].notifiers, ["webhook"]);
+        pending.mark_escalated(&escalated);
+        assert!(escalations.due(&pending, 9000).is_empty());
+    }
+
+    // Quiet time is the caller's call, apply only acts on the action
+    fn quiet(action: QuietAction) -> QuietHours {
+        QuietHours::compile(&QuietHoursConfig {
+            action,
+            ..QuietHoursConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_hold_steps_back_without_losing_them() {
+        let escalations = Escalations::new(EscalationConfig {
+            normal: vec![step(600, &["webhook"])],
+            ..EscalationConfig::default()
+        });
+        let mut pending = PendingEvents::default();
+        pending.add(
+            &[NotifyEvent::sample("1720428655.000200", Priority::Normal)],
+            1000,
+        );
+
+        // Suppressed, so not marked: due again once the quiet time ends
+        let escalated = escalations.due(&pending, 1600);
+        let (sent, held) = quiet(QuietAction::Suppress).apply_escalations(escalated);
+        assert_eq!((sent.len(), held), (0, 1));
+        pending.mark_escalated(&sent);
+        assert_eq!(escalations.due(&pending, 1700).len(), 1);
+
+        // Downgraded, still sent to the normal step's notifiers
+        let escalated = escalations.due(&pending, 1700);
+        let (sent, _) = quiet(QuietAction::Downgrade).apply_escalations(escalated);
+        assert_eq!(sent[0].event.priority, Priority::Low);
+        assert_eq!(sent[0].notifiers, ["webhook"]);
+        pending.mark_escalated(&sent);
+        assert!(escalations.due(&pending, 1800).is_empty());
+        assert_eq!(pending.events()[0].event.priority, Priority::Normal);
+        assert_eq!(pending.events()[0].event.escalation, 1);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::conversation::errors_str::SlackChannelError;
+use crate::conversation::messages_str::MessageNormal;
+
+use super::{
+    channels_service::{Channel, Message},
+    mrkdwn_service::MrkdwnRenderer,
+};
+
+#[derive(Debug, Clone)]
+pub struct ThreadExport {
+    pub parent: MessageNormal,
+    pub replies: Vec<MessageNormal>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelExport {
+    pub channel: Channel,
+    // Oldest first
+    pub threads: Vec<ThreadExport>,
+}
+
+impl ChannelExport {
+    // Loads the channel history between the timestamps with all of its threads.
+    pub async fn load(
+        channel: Channel,
+        since: u64,
+        until: Option<u64>,
+    ) -> Result<ChannelExport, SlackChannelError> {
+        let history = channel.load_range(since, until).await?;
+
+        let mut threads = Vec::new();
+        for parent in history {
+            let replies = if parent.reply_count.unwrap_or(0) > 0 {
+                Channel::load_thread(&channel.channel_id, &parent.ts)
+                    .await?
+                    .into_iter()
+                    .filter(|m| m.ts != parent.ts)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            threads.push(ThreadExport { parent, replies });
+        }
+
+        Ok(ChannelExport { channel, threads })
+    }
+
+    // The channel name without what is unsafe in a file name, else the channel id.
+    pub fn file_name(&self, extension: &str) -> String {
+        let name: String = self
+            .channel
+            .name
+            .chars()
+            .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            .collect();
+        let name = name.trim_start_matches('.');
+        if name.is_empty() {
+            return format!("{}.{}", self.channel.channel_id, extension);
+        }
+
+        format!("{}.{}", name, extension)
+    }
+
+    // One raw slack message per line, each parent followed by its replies.
+    pub fn to_jsonl(&self) -> String {
+        let mut lines = Vec::new();
+        for thread in self.threads.iter() {
+            for message in std::iter::once(&thread.parent).chain(thread.replies.iter()) {
+                match serde_json::to_string(message) {
+                    Ok(line) => lines.push(line),
+                    Err(e) => eprintln!("Skipping message {}. {:?}", message.ts, e),
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    pub fn to_markdown(&self, names: &MrkdwnRenderer) -> String {
+        let mut markdown = format!("# #{}\n", self.channel.name);
+
+        for thread in self.threads.iter() {
+            markdown.push_str(&format!(
+                "\n**{}** _{}_\n\n{}\n",
+                ChannelExport::author(&thread.parent, names),
+                Message::display_time(Message::parse_ts(&thread.parent.ts)),
+                names.to_plain(&thread.parent.text)
+            ));
+            for reply in thread.replies.iter() {
+                let text = names.to_plain(&reply.text).replace('\n', "\n> ");
+                markdown.push_str(&format!(
+                    "\n> **{}** _{}_\n>\n> {}\n",
+                    ChannelExport::author(reply, names),
+                    Message::display_time(Message::parse_ts(&reply.ts)),
+                    text
+                ));
+            }
+        }
+
+        markdown
+    }
+
+    // A single page with inline styles, no external resources.
+    pub fn to_html(&self, names: &MrkdwnRenderer) -> String {
+        let title = escape_html(&format!("#{}", self.channel.name));
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, HTML_STYLE, title
+        );
+
+        for thread in self.threads.iter() {
+            html.push_str("<article>\n");
+            html.push_str(&ChannelExport::html_message(&thread.parent, names));
+            if !thread.replies.is_empty() {
+                html.push_str("<section class=\"replies\">\n");
+                for reply in thread.replies.iter() {
+                    html.push_str(&ChannelExport::html_message(reply, names));
+                }
+                html.push_str("</section>\n");
+            }
+            html.push_str("</article>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+
+        html
+    }
+
+    fn html_message(message: &MessageNormal, names: &MrkdwnRenderer) -> String {
+        format!(
+            "<div class=\"message\"><span class=\"author\">{}</span> <time>{}</time><p>{}</p></div>\n",
+            escape_html(&ChannelExport::author(message, names)),
+            Message::display_time(Message::parse_ts(&message.ts)),
+            escape_html(&names.to_plain(&message.text)).replace('\n', "<br>")
+        )
+    }
+
+    fn author(message: &MessageNormal, names: &MrkdwnRenderer) -> String {
+        match (&message.user, &message.bot_id) {
+            (Some(user), _) => names.user_name(user),
+            (None, Some(bot)) => bot.clone(),
+            (None, None) => String::from("unknown"),
+        }
+    }
+}
+
+static HTML_STYLE: &str =
+    "body { font-family: sans-serif; max-width: 50em; margin: 2em auto; color: #1d1c1d; }
+article { border-bottom: 1px solid #ddd; padding: 0.5em 0; }
+.author { font-weight: bold; }
+time { color: #616061; font-size: 0.85em; }
+p { margin: 0.3em 0; white-space: normal; }
+.replies { margin-left: 2em; border-left: 3px solid #ddd; padding-left: 1em; }";
+
+// For text already rendered, without slack's escapes.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conversation::entity::users::User;
+
+    fn resolver() -> MrkdwnRenderer {
+        MrkdwnRenderer::new(
+            &[User::new("U04853SN1AP".parse().unwrap(), "Ana", false)],
+            &[Channel::new(
+                "feature-navigation".into(),
+                "C07BSNU3GG1".parse().unwrap(),
+                false,
+            )],
+        )
+    }
+
+    fn message(ts: &str, text: &str) -> MessageNormal {
+        let serialized = format!(
+            "{{\"user\": \"U04853SN1AP\", \"type\": \"message\", \"ts\": \"{}\", \"text\": \"{}\"}}",
+            ts, text
+        );
+        serde_json::from_str(&serialized).unwrap()
+    }
+
+    #[test]
+    fn renders_threads() {
+        let export = ChannelExport {
+            channel: Channel::new(
+                "feature-navigation".into(),
+                "C07BSNU3GG1".parse().unwrap(),
+                false,
+            ),
+            threads: vec![ThreadExport {
+                parent: message("1720428655.000200", "Deploy <@U04853SN1AP> & go"),
+                replies: vec![message("1720428700.000200", "done")],
+            }],
+        };
+        let names = resolver();
+
+        assert_eq!(export.to_jsonl().lines().count(), 2);
+        let markdown = export.to_markdown(&names);
+        assert!(markdown.starts_with("# #feature-navigation\n"));
+        assert!(markdown.contains("Deploy @Ana & go"));
+        assert!(markdown.contains("> done"));
+        let html = export.to_html(&names);
+        assert!(html.contains("<p>Deploy @Ana &amp; go</p>"));
+        assert!(html.contains("<section class=\"replies\">"));
+    }
+
+    #[test]
+    fn names_the_file_after_the_channel() {
+        let export = |name: &str| ChannelExport {
+            channel: Channel::new(name.into(), "D0000000001".parse().unwrap(), false),
+            threads: Vec::new(),
+        };
+
+        assert_eq!(export("general").file_name("md"), "general.md");
+        assert_eq!(export("@ana").file_name("html"), "ana.html");
+        assert_eq!(export("../ana/x").file_name("md"), "anax.md");
+        assert_eq!(export("").file_name("md"), "D0000000001.md");
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::conversation::errors_str::FileSystemError;
+use crate::conversation::services::state_fs::{read_state, write_state};
+
+use super::notification::NotifyEvent;
+
+static FILE_NAME: &str = "notify_ledger.json";
+
+// When every event key last notified, so a busy thread does not notify every cycle.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyLedger {
+    // Event key to seconds since epoch
+    notified: HashMap<String, u64>,
+}
+
+impl NotifyLedger {
+    pub fn load() -> NotifyLedger {
+        match read_state(FILE_NAME) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("\x1b[93m{}, starting a new one\x1b[0m", e);
+                NotifyLedger::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), FileSystemError> {
+        write_state(FILE_NAME, self)
+    }
+
+    // Drops the events notified less than cooldown_secs ago, or twice in the same cycle.
+    pub fn filter(
+        &mut self,
+        events: Vec<NotifyEvent>,
+        now: u64,
+        cooldown_secs: u64,
+    ) -> (Vec<NotifyEvent>, usize) {
+        // Entries past their cooldown no longer hold anything back
+        self.notified
+            .retain(|_, at| now.saturating_sub(*at) < cooldown_secs);
+
+        let total = events.len();
+        let mut kept = Vec::new();
+        for event in events {
+            let key = event.key();
+            if kept.iter().any(|k: &NotifyEvent| k.key() == key) {
+                continue;
+            }
+            if self.notified.contains_key(&key) {
+                continue;
+            }
+            self.notified.insert(key, now);
+            kept.push(event);
+        }
+        let dropped = total - kept.len();
+
+        (kept, dropped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conversation::entity::notification::Priority;
+
+    fn in_thread(ts: &str) -> NotifyEvent {
+        let mut event = NotifyEvent::sample(ts, Priority::Normal);
+        event.thread_ts = Some("1720428655.000200".into());
+        event
+    }
+
+    #[test]
+    fn notifies_once_per_thread_until_the_cooldown_ends() {
+        let mut ledger = NotifyLedger::default();
+
+        let (kept, dropped) = ledger.filter(
+            vec![
+                in_thread("1720428700.000200"),
+                in_thread("1720428710.000200"),
+            ],
+            1000,
+            600,
+        );
+        assert_eq!((kept.len(), dropped), (1, 1));
+
+        let (kept, _) = ledger.filter(vec![in_thread("1720428800.000200")], 1500, 600);
+        assert!(kept.is_empty());
+
+        let (kept, _) = ledger.filter(vec![in_thread("1720428900.000200")], 1600, 600);
+        assert_eq!(kept.len(), 1);
+
+        // Other messages are other keys
+        let (kept, _) = ledger.filter(
+            vec![NotifyEvent::sample("1720428950.000200", Priority::Normal)],
+            1600,
+            600,
+        );
+        assert_eq!(kept.len(), 1);
+    }
+}
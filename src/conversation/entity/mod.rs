@@ -1,3 +1,19 @@
+pub mod archive_service;
 pub mod channels_service;
-pub mod users_service;
+pub mod digest_service;
+pub mod directory_service;
+pub mod doctor_service;
+pub mod escalation_service;
+pub mod export_service;
+pub mod ledger_service;
+pub mod mrkdwn_service;
+pub mod notification;
+pub mod notify_service;
+pub mod pending_service;
+pub mod permalink_service;
+pub mod quiet_hours_service;
+pub mod rules_service;
+pub mod search_service;
 pub mod users;
+pub mod users_service;
+pub mod watch_service;
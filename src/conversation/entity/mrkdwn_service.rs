@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::conversation::services::channels_cache_fs;
+
+use super::{channels_service::Channel, directory_service::UserDirectory, users::User};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Plain,
+    Ansi,
+}
+
+// Slack mrkdwn made readable: names for the ids, links, entities, emoji and formatting.
+pub struct MrkdwnRenderer {
+    users: HashMap<String, String>,
+    channels: HashMap<String, String>,
+}
+
+impl MrkdwnRenderer {
+    pub fn new(users: &[User], channels: &[Channel]) -> MrkdwnRenderer {
+        MrkdwnRenderer {
+            users: users
+                .iter()
+                .map(|u| (u.id().to_string(), u.name().to_string()))
+                .collect(),
+            channels: channels
+                .iter()
+                .map(|c| (c.channel_id.to_string(), c.name.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn from_caches() -> MrkdwnRenderer {
+        MrkdwnRenderer::from_directory(&UserDirectory::load())
+    }
+
+    // The directory's users, with the channels of the cache.
+    pub fn from_directory(directory: &UserDirectory) -> MrkdwnRenderer {
+        let channels: Vec<Channel> = channels_cache_fs::read_cache()
+            .unwrap_or_default()
+            .iter()
+            .map(|c| c.into())
+            .collect();
+        let users: Vec<User> = directory.users().into_iter().cloned().collect();
+
+        MrkdwnRenderer::new(&users, &channels)
+    }
+
+    pub fn user_name(&self, user_id: &str) -> String {
+        self.users
+            .get(user_id)
+            .cloned()
+            .unwrap_or(user_id.to_string())
+    }
+
+    pub fn channel_name(&self, channel_id: &str) -> String {
+        self.channels
+            .get(channel_id)
+            .cloned()
+            .unwrap_or(channel_id.to_string())
+    }
+
+    pub fn to_plain(&self, text: &str) -> String {
+        self.render(text, Style::Plain)
+    }
+
+    // Bold, italics, strikes, code and links as terminal escape codes.
+    pub fn to_ansi(&self, text: &str) -> String {
+        self.render(text, Style::Ansi)
+    }
+
+    // Ansi when printing to a terminal, plain when piped.
+    pub fn to_terminal(&self, text: &str) -> String {
+        if std::io::stdout().is_terminal() {
+            self.to_ansi(text)
+        } else {
+            self.to_plain(text)
+        }
+    }
+
+    fn render(&self, text: &str, style: Style) -> String {
+        let mut rendered = Vec::new();
+        // Odd parts are inside ``` blocks, left as they are
+        for (i, part) in text.split("```").enumerate() {
+            if i % 2 == 1 {
+                rendered.push(code_block(&decode_entities(part), style));
+                continue;
+            }
+            let lines: Vec<String> = self
+                .references(part, style)
+                .split('\n')
+                .map(|line| render_line(line, style))
+                .collect();
+            rendered.push(lines.join("\n"));
+        }
+
+        rendered.join("")
+    }
+
+    // Replaces `<@U…>`, `<#C…|name>`, `<!here>` and `<url|label>`, an unclosed `<` is kept.
+    fn references(&self, text: &str, style: Style) -> String {
+        let mut resolved = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find('<') {
+            resolved.push_str(&rest[..start]);
+            let end = match rest[start..].find('>') {
+                Some(e) => start + e,
+                None => {
+                    rest = &rest[start..];
+                    break;
+                }
+            };
+
+            let inner = &rest[start + 1..end];
+            let (id, label) = match inner.split_once('|') {
+                Some((id, label)) => (id, Some(label)),
+                None => (inner, None),
+            };
+            let mention = if let Some(user_id) = id.strip_prefix('@') {
+                Some(format!("@{}", self.user_name(user_id)))
+            } else if let Some(channel_id) = id.strip_prefix('#') {
+                let name = match label {
+                    Some(l) if !self.channels.contains_key(channel_id) => l.to_string(),
+                    _ => self.channel_name(channel_id),
+                };
+                Some(format!("#{}", name))
+            } else {
+                // <!subteam^S…|@team>, <!date^…|fallback>, <!here>
+                id.strip_prefix('!').map(|command| match label {
+                    Some(l) => l.to_string(),
+                    None => format!("@{}", command.split('^').next().unwrap_or(command)),
+                })
+            };
+
+            match mention {
+                Some(m) if style == Style::Ansi => {
+                    resolved.push_str(&format!("\x1b[1m{}\x1b[22m", m))
+                }
+                Some(m) => resolved.push_str(&m),
+                None => resolved.push_str(&link(id, label, style)),
+            }
+            rest = &rest[end + 1..];
+        }
+        resolved.push_str(rest);
+
+        resolved
+    }
+}
+
+fn link(url: &str, label: Option<&str>, style: Style) -> String {
+    let shown = url.strip_prefix("mailto:").unwrap_or(url);
+    match (label.filter(|l| *l != url && *l != shown), style) {
+        (Some(l), Style::Plain) => format!("{} ({})", l, shown),
+        (Some(l), Style::Ansi) => format!("\x1b[4m{}\x1b[24m ({})", l, shown),
+        (None, Style::Plain) => shown.to_string(),
+        (None, Style::Ansi) => format!("\x1b[4m{}\x1b[24m", shown),
+    }
+}
+
+fn code_block(code: &str, style: Style) -> String {
+    match style {
+        Style::Plain => code.to_string(),
+        Style::Ansi => code
+            .split('\n')
+            .map(|l| format!("\x1b[36m{}\x1b[39m", l))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    }
+}
+
+fn render_line(line: &str, style: Style) -> String {
+    let line = emoji_outside_code(line);
+    // Quotes arrive escaped, `&gt; quoted`
+    let (quote, line) = match line.strip_prefix("&gt;") {
+        Some(quoted) => (true, quoted.trim_start().to_string()),
+        None => (false, line),
+    };
+    let rendered = decode_entities(&inline(&line, style));
+
+    match (quote, style) {
+        (false, _) => rendered,
+        (true, Style::Plain) => format!("> {}", rendered),
+        (true, Style::Ansi) => format!("\x1b[2m│\x1b[22m {}", rendered),
+    }
+}
+
+// `*bold*`, `_italic_`, `~strike~` and `` `code` ``, only between word boundaries.
+fn inline(line: &str, style: Style) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut rendered = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let marker = chars[i];
+        let codes = match (marker, style) {
+            ('*' | '_' | '~' | '`', Style::Plain) => Some(("", "")),
+            ('*', Style::Ansi) => Some(("\x1b[1m", "\x1b[22m")),
+            ('_', Style::Ansi) => Some(("\x1b[3m", "\x1b[23m")),
+            ('~', Style::Ansi) => Some(("\x1b[9m", "\x1b[29m")),
+            ('`', Style::Ansi) => Some(("\x1b[36m", "\x1b[39m")),
+            _ => None,
+        };
+        let opens = i == 0 || !chars[i - 1].is_alphanumeric();
+        let next = chars.get(i + 1);
+        if let (Some((open, close)), true, Some(next)) = (codes, opens, next) {
+            let closing = (i + 2..chars.len()).find(|&j| {
+                chars[j] == marker
+                    && !chars[j - 1].is_whitespace()
+                    && chars.get(j + 1).is_none_or(|c| !c.is_alphanumeric())
+            });
+            if let (false, Some(j)) = (next.is_whitespace() || *next == marker, closing) {
+                let inner: String = chars[i + 1..j].iter().collect();
+                let inner = if marker == '`' {
+                    inner
+                } else {
+                    inline(&inner, style)
+                };
+                rendered.push_str(open);
+                rendered.push_str(&inner);
+                rendered.push_str(close);
+                i = j + 1;
+                continue;
+            }
+        }
+        rendered.push(marker);
+        i += 1;
+    }
+
+    rendered
+}
+
+// Slack escapes only `&`, `<` and `>`.
+pub fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+static EMOJI: [(&str, &str); 64] = [
+    ("+1", "👍"),
+    ("thumbsup", "👍"),
+    ("-1", "👎"),
+    ("thumbsdown", "👎"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("grinning", "😀"),
+    ("laughing", "😆"),
+    ("joy", "😂"),
+    ("rofl", "🤣"),
+    ("slightly_smiling_face", "🙂"),
+    ("wink", "😉"),
+    ("blush", "😊"),
+    ("heart_eyes", "😍"),
+    ("thinking_face", "🤔"),
+    ("neutral_face", "😐"),
+    ("unamused", "😒"),
+    ("sweat_smile", "😅"),
+    ("cry", "😢"),
+    ("sob", "😭"),
+    ("scream", "😱"),
+    ("rage", "😡"),
+    ("sunglasses", "😎"),
+    ("upside_down_face", "🙃"),
+    ("face_palm", "🤦"),
+    ("shrug", "🤷"),
+    ("pray", "🙏"),
+    ("clap", "👏"),
+    ("wave", "👋"),
+    ("ok_hand", "👌"),
+    ("muscle", "💪"),
+    ("raised_hands", "🙌"),
+    ("point_up", "☝️"),
+    ("eyes", "👀"),
+    ("heart", "❤️"),
+    ("broken_heart", "💔"),
+    ("fire", "🔥"),
+    ("tada", "🎉"),
+    ("rocket", "🚀"),
+    ("star", "⭐"),
+    ("sparkles", "✨"),
+    ("100", "💯"),
+    ("white_check_mark", "✅"),
+    ("heavy_check_mark", "✔️"),
+    ("x", "❌"),
+    ("warning", "⚠️"),
+    ("rotating_light", "🚨"),
+    ("no_entry", "⛔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("memo", "📝"),
+    ("calendar", "📆"),
+    ("hourglass", "⌛"),
+    ("coffee", "☕"),
+    ("beer", "🍺"),
+    ("pizza", "🍕"),
+    ("wrench", "🔧"),
+    ("lock", "🔒"),
+    ("key", "🔑"),
+    ("link", "🔗"),
+    ("question", "❓"),
+    ("exclamation", "❗"),
+    ("arrow_right", "➡️"),
+    ("ship", "🚢"),
+];
+
+// Emoji outside `code` spans, a backtick without its pair does not open one.
+fn emoji_outside_code(line: &str) -> String {
+    let parts: Vec<&str> = line.split('`').collect();
+    // Parts after a paired backtick are code
+    let paired = (parts.len() - 1) / 2 * 2;
+    let mut rendered = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i % 2 == 1 && i <= paired {
+            rendered.push(part.to_string());
+        } else {
+            rendered.push(emoji(part));
+        }
+    }
+
+    rendered.join("`")
+}
+
+// Known `:shortcodes:` to unicode, skin tones dropped, unknown ones kept.
+fn emoji(text: &str) -> String {
+    static SHORTCODE: OnceLock<Regex> = OnceLock::new();
+    let shortcode =
+        SHORTCODE.get_or_init(|| Regex::new(r":([a-z0-9_+\-]+):(:skin-tone-\d:)?").unwrap());
+
+    shortcode
+        .replace_all(text, |caps: &regex::Captures| {
+            match EMOJI.iter().find(|(code, _)| *code == &caps[1]) {
+                Some((_, unicode)) => unicode.to_string(),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn renderer() -> MrkdwnRenderer {
+        MrkdwnRenderer::new(
+            &[User::new("U04853SN1AP".parse().unwrap(), "Ana", false)],
+            &[Channel::new(
+                "feature-navigation".into(),
+                "C07BSNU3GG1".parse().unwrap(),
+                false,
+            )],
+        )
+    }
+
+    #[test]
+    fn resolves_user_and_channel_references() {
+        let names = renderer();
+
+        assert_eq!(
+            names.to_plain("<@U04853SN1AP> see <#C07BSNU3GG1> and <#C0000000000|other>"),
+            "@Ana see #feature-navigation and #other"
+        );
+        assert_eq!(
+            names.to_plain("<@U0000000000> <https://example.com|site> <open"),
+            "@U0000000000 site (https://example.com) <open"
+        );
+        assert_eq!(
+            names.to_plain(
+                "<!here> <!subteam^S0614TZR7|@oncall> <mailto:ana@example.com|ana@example.com>"
+            ),
+            "@here @oncall ana@example.com"
+        );
+    }
+
+    #[test]
+    fn renders_plain_text() {
+        let names = renderer();
+
+        assert_eq!(
+            names.to_plain(
+                "*Deploy* is _done_ &amp; ~not~ `a*b*c` :tada: :+1::skin-tone-3: :custom:"
+            ),
+            "Deploy is done & not a*b*c 🎉 👍 :custom:"
+        );
+        assert_eq!(
+            names.to_plain("`a :tada: b` :tada: ```:tada:``` don't :tada:"),
+            "a :tada: b 🎉 :tada: don't 🎉"
+        );
+        assert_eq!(
+            names.to_plain("2*3*4 snake_case_name"),
+            "2*3*4 snake_case_name"
+        );
+        assert_eq!(
+            names.to_plain("&gt; quoted\n```let a = *b*;\nx &lt; y```"),
+            "> quoted\nlet a = *b*;\nx < y"
+        );
+    }
+
+    #[test]
+    fn renders_ansi() {
+        let names = renderer();
+
+        assert_eq!(
+            names.to_ansi("*hi* <@U04853SN1AP> <https://example.com>"),
+            "\x1b[1mhi\x1b[22m \x1b[1m@Ana\x1b[22m \x1b[4mhttps://example.com\x1b[24m"
+        );
+        assert_eq!(
+            names.to_ansi("_a *b*_"),
+            "\x1b[3ma \x1b[1mb\x1b[22m\x1b[23m"
+        );
+    }
+}
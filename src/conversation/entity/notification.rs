@@ -0,0 +1,444 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::PrioritiesConfig;
+use crate::conversation::errors_str::NotifyError;
+use crate::conversation::ids_str::{ChannelId, UserId};
+
+use super::{
+    channels_service::{is_direct, Message},
+    digest_service::Digest,
+    rules_service::RuleSet,
+};
+
+// Ordered, the highest priority of a cycle is its max.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    #[default]
+    Low,
+    Normal,
+    Urgent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    // <@me> in the text
+    DirectMention,
+    // Message in a D… conversation
+    DirectMessage,
+    // New replies in a thread involving followed users
+    ThreadReply,
+    // A [[rules]] entry matched
+    Keyword,
+    // Sent by, or involving, a followed user
+    VipSender,
+    // <!channel>, <!here> or <!everyone>
+    Broadcast,
+}
+
+impl Reason {
+    pub fn priority(&self, priorities: &PrioritiesConfig) -> Priority {
+        match self {
+            Reason::DirectMention => priorities.direct_mention,
+            Reason::DirectMessage => priorities.direct_message,
+            Reason::ThreadReply => priorities.thread_reply,
+            Reason::Keyword => priorities.keyword,
+            Reason::VipSender => priorities.vip_sender,
+            Reason::Broadcast => priorities.broadcast,
+        }
+    }
+}
+
+// Something worth telling, one per message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifyEvent {
+    pub priority: Priority,
+    pub reason: Reason,
+    // Every reason that applied, the highest priority one first
+    pub reasons: Vec<Reason>,
+    pub channel_id: ChannelId,
+    pub channel_name: String,
+    pub ts: String,
+    // Parent message when the event comes from a thread
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_ts: Option<String>,
+    // None for bots and integrations
+    #[serde(default)]
+    pub sender: Option<UserId>,
+    // The sender's display name, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender_name: Option<String>,
+    pub text: String,
+    // Followed users involved in the message
+    pub users: Vec<UserId>,
+    // Keyword rules matching the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<String>,
+    pub permalink: Option<String>,
+    // slack:// link opening the message in the desktop app
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deep_link: Option<String>,
+    // Escalation steps reached while pending, 0 when first notified
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub escalation: usize,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+impl NotifyEvent {
+    // Stable across cycles, receivers use it to drop repeated events.
+    pub fn id(&self) -> String {
+        format!("{}:{}:{:?}", self.channel_id, self.ts, self.reason).to_lowercase()
+    }
+
+    // The sender's name, else the id.
+    pub fn sender_label(&self) -> &str {
+        self.sender_name
+            .as_deref()
+            .or(self.sender.as_deref())
+            .unwrap_or("a bot")
+    }
+
+    // Channel, thread and reason. Replies in a thread share it, so the thread notifies once.
+    pub fn key(&self) -> String {
+        format!(
+            "{}:{}:{:?}",
+            self.channel_id,
+            self.thread_ts.as_ref().unwrap_or(&self.ts),
+            self.reason
+        )
+        .to_lowercase()
+    }
+
+    #[cfg(test)]
+    pub fn sample(ts: &str, priority: Priority) -> NotifyEvent {
+        NotifyEvent {
+            priority,
+            reason: Reason::DirectMention,
+            reasons: vec![Reason::DirectMention],
+            channel_id: "C07BSNU3GG1".parse().unwrap(),
+            channel_name: "feature-navigation".into(),
+            ts: ts.into(),
+            thread_ts: None,
+            sender: "U04853SN1AP".parse().ok(),
+            sender_name: None,
+            text: "prod down".into(),
+            users: Vec::new(),
+            rules: Vec::new(),
+            permalink: None,
+            deep_link: None,
+            escalation: 0,
+        }
+    }
+}
+
+// What is waiting for attention after a cycle, for indicators.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotifyState {
+    pub pending: bool,
+    pub priority: Option<Priority>,
+    pub events: usize,
+    // Highest escalation step among the events, for a louder indicator
+    pub escalation: usize,
+    // Seconds since epoch
+    pub updated_at: u64,
+}
+
+impl NotifyState {
+    pub fn from_events(events: &[&NotifyEvent], updated_at: u64) -> NotifyState {
+        NotifyState {
+            pending: !events.is_empty(),
+            priority: events.iter().map(|e| e.priority).max(),
+            events: events.len(),
+            escalation: events.iter().map(|e| e.escalation).max().unwrap_or(0),
+            updated_at,
+        }
+    }
+}
+
+// Where the events of a cycle go: the console, a file, a command, a broker...
+pub trait Notifier {
+    // Shown when the backend fails
+    fn name(&self) -> String;
+
+    fn notify(&mut self, event: &NotifyEvent) -> Result<(), NotifyError>;
+
+    // Every event of a cycle, for backends that group them. A failing event does not stop the others.
+    fn notify_all(&mut self, events: &[&NotifyEvent]) -> Result<(), NotifyError> {
+        let mut failed = 0;
+        for event in events {
+            if let Err(e) = self.notify(event) {
+                eprintln!(
+                    "\x1b[93m{} failed for {}. {}\x1b[0m",
+                    self.name(),
+                    event.id(),
+                    e
+                );
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(NotifyError::new(&format!(
+                "{} of {} events failed",
+                failed,
+                events.len()
+            )));
+        }
+        Ok(())
+    }
+
+    // Called after every cycle, with or without events.
+    fn state(&mut self, _state: &NotifyState) -> Result<(), NotifyError> {
+        Ok(())
+    }
+
+    // The periodic activity digest. Backends without a way to show it skip it.
+    fn digest(&mut self, _digest: &Digest) -> Result<(), NotifyError> {
+        Ok(())
+    }
+
+    // Sends what is still queued before the program exits.
+    fn close(&mut self) {}
+}
+
+// What makes a message worth a notification.
+pub struct Triggers<'a> {
+    pub my_user_id: Option<&'a UserId>,
+    pub followed: &'a [&'a UserId],
+    pub rules: &'a RuleSet,
+    pub priorities: &'a PrioritiesConfig,
+    // Whether involving followed users is enough
+    pub on_followed: bool,
+    // Whether every direct message is enough
+    pub on_direct: bool,
+}
+
+static BROADCASTS: [&str; 3] = ["<!channel>", "<!here>", "<!everyone>"];
+
+impl Triggers<'_> {
+    // The event for a message, if any. Own messages never notify.
+    pub fn detect(
+        &self,
+        channel_id: &ChannelId,
+        channel_name: &str,
+        message: &Message,
+        thread_ts: Option<&str>,
+    ) -> Option<NotifyEvent> {
+        if self.my_user_id.is_some() && self.my_user_id == message.sender.as_ref() {
+            return None;
+        }
+
+        let mut reasons = Vec::new();
+        if self.on_direct && is_direct(channel_id, channel_name) {
+            reasons.push(Reason::DirectMessage);
+        }
+        if let Some(me) = self.my_user_id {
+            if message.message.contains(&format!("<@{}>", me)) {
+                reasons.push(Reason::DirectMention);
+            }
+        }
+        let users = self.followed_users(message);
+        if self.on_followed && !users.is_empty() {
+            reasons.push(Reason::VipSender);
+        }
+        let rules = self
+            .rules
+            .matching(channel_id, channel_name, &message.message);
+        if !rules.is_empty() {
+            reasons.push(Reason::Keyword);
+        }
+        if BROADCASTS.iter().any(|b| message.message.contains(b)) {
+            reasons.push(Reason::Broadcast);
+        }
+
+        self.event(reasons, channel_id, channel_name, message, thread_ts)
+            .map(|mut e| {
+                e.users = users;
+                e.rules = rules;
+                e
+            })
+    }
+
+    // The event for new replies in a thread whose parent involves followed users.
+    pub fn thread_reply(
+        &self,
+        channel_id: &ChannelId,
+        channel_name: &str,
+        parent: &Message,
+    ) -> Option<NotifyEvent> {
+        let users = self.followed_users(parent);
+        if users.is_empty() {
+            return None;
+        }
+
+        self.event(
+            vec![Reason::ThreadReply],
+            channel_id,
+            channel_name,
+            parent,
+            Some(&parent.received_ts),
+        )
+        .map(|mut e| {
+            e.users = users;
+            e
+        })
+    }
+
+    fn followed_users(&self, message: &Message) -> Vec<UserId> {
+        message
+            .users_list()
+            .into_iter()
+            .filter(|u| self.followed.contains(&u))
+            .collect()
+    }
+
+    fn event(
+        &self,
+        mut reasons: Vec<Reason>,
+        channel_id: &ChannelId,
+        channel_name: &str,
+        message: &Message,
+        thread_ts: Option<&str>,
+    ) -> Option<NotifyEvent> {
+        // Stable, ties keep the detection order
+        reasons.sort_by_key(|r| std::cmp::Reverse(r.priority(self.priorities)));
+        let reason = *reasons.first()?;
+
+        Some(NotifyEvent {
+            priority: reason.priority(self.priorities),
+            reason,
+            reasons,
+            channel_id: channel_id.clone(),
+            channel_name: channel_name.to_string(),
+            ts: message.received_ts.clone(),
+            thread_ts: thread_ts.map(|t| t.to_string()),
+            sender: message.sender.clone(),
+            sender_name: None,
+            text: message.message.clone(),
+            users: Vec::new(),
+            rules: Vec::new(),
+            permalink: None,
+            deep_link: None,
+            escalation: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::RuleConfig;
+    use crate::conversation::messages_str::MessageNormal;
+
+    fn message(user: &str, text: &str) -> Message {
+        let serialized = format!(
+            "{{\"user\": \"{}\", \"type\": \"message\", \"ts\": \"1720428655.000200\", \"text\": \"{}\"}}",
+            user, text
+        );
+        let normal: MessageNormal = serde_json::from_str(&serialized).unwrap();
+        (&normal).into()
+    }
+
+    #[test]
+    fn picks_the_highest_priority_reason() {
+        let priorities = PrioritiesConfig::default();
+        let rules = RuleSet::compile(&[RuleConfig {
+            name: "outage".into(),
+            keywords: vec!["prod down".into()],
+            ..RuleConfig::default()
+        }])
+        .unwrap();
+        let me: UserId = "U0000000ME1".parse().unwrap();
+        let followed: UserId = "U04853SN1AP".parse().unwrap();
+        let triggers = Triggers {
+            my_user_id: Some(&me),
+            followed: &[&followed],
+            rules: &rules,
+            priorities: &priorities,
+            on_followed: true,
+            on_direct: true,
+        };
+
+        let general: ChannelId = "C0000000001".parse().unwrap();
+        let direct: ChannelId = "D0000000001".parse().unwrap();
+        let group: ChannelId = "G0000000001".parse().unwrap();
+        let event = triggers
+            .detect(
+                &general,
+                "general",
+                &message("U04853SN1AP", "<!here> prod down, <@U0000000ME1>?"),
+                None,
+            )
+            .unwrap();
+        assert_eq!(event.reason, Reason::DirectMention);
+        assert_eq!(event.priority, Priority::Urgent);
+        assert_eq!(
+            event.reasons,
+            vec![
+                Reason::DirectMention,
+                Reason::VipSender,
+                Reason::Keyword,
+                Reason::Broadcast
+            ]
+        );
+        assert_eq!(event.rules, vec!["outage"]);
+
+        let event = triggers
+            .detect(
+                &general,
+                "general",
+                &message("U0000000002", "<!channel> lunch"),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            (event.reason, event.priority),
+            (Reason::Broadcast, Priority::Low)
+        );
+
+        // Bots and integrations have no user, their alerts still notify
+        let event = triggers
+            .detect(
+                &general,
+                "general",
+                &message("B0000000001", "prod down"),
+                None,
+            )
+            .unwrap();
+        assert_eq!(event.reason, Reason::Keyword);
+        assert_eq!(event.sender, None);
+        assert_eq!(event.sender_label(), "a bot");
+
+        let event = triggers
+            .detect(&direct, "", &message("U0000000002", "hi"), None)
+            .unwrap();
+        assert_eq!(event.reason, Reason::DirectMessage);
+        let event = triggers
+            .detect(
+                &group,
+                "mpdm-ana--bob-1",
+                &message("U0000000002", "hi all"),
+                None,
+            )
+            .unwrap();
+        assert_eq!(event.reason, Reason::DirectMessage);
+
+        assert!(triggers
+            .detect(
+                &general,
+                "general",
+                &message("U0000000002", "nothing"),
+                None
+            )
+            .is_none());
+        assert!(triggers
+            .detect(&direct, "", &message("U0000000ME1", "mine"), None)
+            .is_none());
+    }
+}
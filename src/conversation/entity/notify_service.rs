@@ -0,0 +1,242 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{self, NotifierConfig};
+use crate::conversation::services::{
+    notify_command::CommandNotifier, notify_console::ConsoleNotifier, notify_file::FileNotifier,
+    notify_mqtt::MqttNotifier, notify_webhook::WebhookNotifier,
+};
+
+use super::digest_service::Digest;
+use super::escalation_service::Escalation;
+use super::notification::{Notifier, NotifyEvent, NotifyState, Priority};
+
+// Every configured backend with its type and the lowest priority it takes.
+pub struct Notifiers {
+    backends: Vec<(&'static str, Priority, Box<dyn Notifier>)>,
+}
+
+impl Notifiers {
+    pub fn new(backends: Vec<(&'static str, Priority, Box<dyn Notifier>)>) -> Notifiers {
+        Notifiers { backends }
+    }
+
+    // The [[notifiers]] entries. Without console the json summary of `watch --once` stays clean.
+    pub fn from_config(console: bool) -> Notifiers {
+        let mut backends: Vec<(&'static str, Priority, Box<dyn Notifier>)> = Vec::new();
+
+        for notifier in config::get().notifiers.iter() {
+            let (min_priority, backend): (Priority, Box<dyn Notifier>) = match notifier {
+                NotifierConfig::Console { min_priority } => {
+                    if !console {
+                        continue;
+                    }
+                    (*min_priority, Box::new(ConsoleNotifier::new()))
+                }
+                NotifierConfig::File { path, min_priority } => {
+                    (*min_priority, Box::new(FileNotifier::new(path)))
+                }
+                NotifierConfig::Command {
+                    command,
+                    min_priority,
+                    timeout_secs,
+                } => (
+                    *min_priority,
+                    Box::new(CommandNotifier::new(command, *timeout_secs)),
+                ),
+                NotifierConfig::Webhook(webhook) => (
+                    webhook.min_priority,
+                    Box::new(WebhookNotifier::start(webhook)),
+                ),
+                NotifierConfig::Mqtt(mqtt) => {
+                    (mqtt.min_priority, Box::new(MqttNotifier::connect(mqtt)))
+                }
+            };
+            backends.push((notifier.kind(), min_priority, backend));
+        }
+
+        Notifiers::new(backends)
+    }
+
+    // Escalated events go to the notifier types of the step they reached, whatever their min_priority.
+    pub fn escalate(&mut self, escalated: &[Escalation]) {
+        for (kind, _, backend) in self.backends.iter_mut() {
+            let selected: Vec<&NotifyEvent> = escalated
+                .iter()
+                .filter(|e| e.notifiers.iter().any(|k| k == kind))
+                .map(|e| &e.event)
+                .collect();
+
+            if !selected.is_empty() {
+                if let Err(e) = backend.notify_all(&selected) {
+                    eprintln!("\x1b[93mNotifier {} failed. {}\x1b[0m", backend.name(), e);
+                }
+            }
+        }
+    }
+
+    // The new events, then the state of the pending ones. A failing backend is reported and does not stop the others.
+    pub fn dispatch(&mut self, events: &[NotifyEvent], pending: &[&NotifyEvent]) {
+        for (_, min_priority, backend) in self.backends.iter_mut() {
+            let selected: Vec<&NotifyEvent> = events
+                .iter()
+                .filter(|e| e.priority >= *min_priority)
+                .collect();
+
+            if !selected.is_empty() {
+                if let Err(e) = backend.notify_all(&selected) {
+                    eprintln!("\x1b[93mNotifier {} failed. {}\x1b[0m", backend.name(), e);
+                }
+            }
+        }
+        self.state(pending);
+    }
+
+    // Unacknowledged events, each backend sees those of its min_priority and above.
+    pub fn state(&mut self, pending: &[&NotifyEvent]) {
+        let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(n) => n.as_secs(),
+            Err(_) => 0,
+        };
+
+        for (_, min_priority, backend) in self.backends.iter_mut() {
+            let selected: Vec<&NotifyEvent> = pending
+                .iter()
+                .filter(|e| e.priority >= *min_priority)
+                .copied()
+                .collect();
+
+            if let Err(e) = backend.state(&NotifyState::from_events(&selected, now)) {
+                eprintln!("\x1b[93mNotifier {} failed. {}\x1b[0m", backend.name(), e);
+            }
+        }
+    }
+
+    // The digest goes to the given notifier types.
+    pub fn digest(&mut self, digest: &Digest, kinds: &[String]) {
+        for (kind, _, backend) in self.backends.iter_mut() {
+            if !kinds.iter().any(|k| k == kind) {
+                continue;
+            }
+            if let Err(e) = backend.digest(digest) {
+                eprintln!("\x1b[93mNotifier {} failed. {}\x1b[0m", backend.name(), e);
+            }
+        }
+    }
+
+    pub fn close(&mut self) {
+        for (_, _, backend) in self.backends.iter_mut() {
+            backend.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::config::{EscalationConfig, EscalationStep};
+    use crate::conversation::entity::escalation_service::Escalations;
+    use crate::conversation::entity::pending_service::PendingEvents;
+    use crate::conversation::errors_str::NotifyError;
+
+    struct Recorder {
+        seen: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl Notifier for Recorder {
+        fn name(&self) -> String {
+            "recorder".into()
+        }
+
+        fn notify(&mut self, event: &NotifyEvent) -> Result<(), NotifyError> {
+            if self.fail {
+                return Err(NotifyError::new("down"));
+            }
+            self.seen.lock().unwrap().push(event.ts.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn filters_by_priority_and_survives_failures() {
+        let all = Arc::new(Mutex::new(Vec::new()));
+        let urgent = Arc::new(Mutex::new(Vec::new()));
+        let mut notifiers = Notifiers::new(vec![
+            (
+                "recorder",
+                Priority::Low,
+                Box::new(Recorder {
+                    seen: Arc::new(Mutex::new(Vec::new())),
+                    fail: true,
+                }),
+            ),
+            (
+                "recorder",
+                Priority::Low,
+                Box::new(Recorder {
+                    seen: all.clone(),
+                    fail: false,
+                }),
+            ),
+            (
+                "recorder",
+                Priority::Urgent,
+                Box::new(Recorder {
+                    seen: urgent.clone(),
+                    fail: false,
+                }),
+            ),
+        ]);
+
+        notifiers.dispatch(
+            &[
+                NotifyEvent::sample("1", Priority::Normal),
+                NotifyEvent::sample("2", Priority::Urgent),
+            ],
+            &[],
+        );
+
+        assert_eq!(*all.lock().unwrap(), vec!["1", "2"]);
+        assert_eq!(*urgent.lock().unwrap(), vec!["2"]);
+    }
+
+    #[test]
+    fn escalates_to_the_step_notifiers_only() {
+        let console = Arc::new(Mutex::new(Vec::new()));
+        let webhook = Arc::new(Mutex::new(Vec::new()));
+        let mut notifiers = Notifiers::new(vec![
+            (
+                "console",
+                Priority::Low,
+                Box::new(Recorder {
+                    seen: console.clone(),
+                    fail: false,
+                }),
+            ),
+            (
+                "webhook",
+                Priority::Urgent,
+                Box::new(Recorder {
+                    seen: webhook.clone(),
+                    fail: false,
+                }),
+            ),
+        ]);
+        let escalations = Escalations::new(EscalationConfig {
+            normal: vec![EscalationStep {
+                after_secs: 600,
+                notifiers: vec!["webhook".into()],
+            }],
+            ..EscalationConfig::default()
+        });
+        let mut pending = PendingEvents::default();
+        pending.add(&[NotifyEvent::sample("1", Priority::Normal)], 1000);
+
+        notifiers.escalate(&escalations.due(&pending, 1600));
+
+        assert!(console.lock().unwrap().is_empty());
+        assert_eq!(*webhook.lock().unwrap(), vec!["1"]);
+    }
+}
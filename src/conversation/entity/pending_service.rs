@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+
+use crate::conversation::errors_str::FileSystemError;
+use crate::conversation::ids_str::{ChannelId, UserId};
+use crate::conversation::messages_str::MessageNormal;
+use crate::conversation::services::state_fs::{read_state, write_state};
+
+use super::channels_service::Message;
+use super::escalation_service::Escalation;
+use super::notification::NotifyEvent;
+
+static FILE_NAME: &str = "pending.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingEvent {
+    #[serde(flatten)]
+    pub event: NotifyEvent,
+    // Seconds since epoch
+    pub notified_at: u64,
+    // When auto_ack last read its thread, 0 before
+    #[serde(default)]
+    pub checked_at: u64,
+}
+
+fn thread_of(event: &NotifyEvent) -> String {
+    event.thread_ts.clone().unwrap_or(event.ts.clone())
+}
+
+// Notified events nobody acknowledged yet. The watcher adds them, `pending ack` clears them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PendingEvents {
+    events: Vec<PendingEvent>,
+}
+
+impl PendingEvents {
+    // Read on every cycle, the ack commands write it from other processes.
+    pub fn load() -> Result<PendingEvents, FileSystemError> {
+        read_state(FILE_NAME)
+    }
+
+    pub fn save(&self) -> Result<(), FileSystemError> {
+        write_state(FILE_NAME, self)
+    }
+
+    pub fn events(&self) -> &[PendingEvent] {
+        &self.events
+    }
+
+    // Records the steps that were sent, so they are not sent again.
+    pub fn mark_escalated(&mut self, escalated: &[Escalation]) {
+        for e in escalated {
+            let id = e.event.id();
+            if let Some(p) = self.events.iter_mut().find(|p| p.event.id() == id) {
+                p.event.escalation = p.event.escalation.max(e.event.escalation);
+            }
+        }
+    }
+
+    pub fn add(&mut self, events: &[NotifyEvent], now: u64) {
+        for event in events {
+            let id = event.id();
+            if !self.events.iter().any(|p| p.event.id() == id) {
+                self.events.push(PendingEvent {
+                    event: event.clone(),
+                    notified_at: now,
+                    checked_at: 0,
+                });
+            }
+        }
+    }
+
+    // By event id, or every event of a message by its ts. How many were acknowledged.
+    pub fn ack(&mut self, id: &str) -> usize {
+        self.remove(|e| e.id() == id.to_lowercase() || e.ts == id)
+    }
+
+    // By channel id or name.
+    pub fn ack_channel(&mut self, channel: &str) -> usize {
+        let channel = channel.trim_start_matches('#');
+        self.remove(|e| e.channel_id == channel || e.channel_name == channel)
+    }
+
+    pub fn ack_all(&mut self) -> usize {
+        self.remove(|_| true)
+    }
+
+    // Channel and ts of the threads, or lone messages, holding pending events. At most `max`, the longest unchecked first.
+    pub fn threads(&self, max: usize) -> Vec<(ChannelId, String)> {
+        let mut threads: Vec<((ChannelId, String), u64)> = Vec::new();
+        for p in self.events.iter() {
+            let thread = (p.event.channel_id.clone(), thread_of(&p.event));
+            match threads.iter_mut().find(|(t, _)| *t == thread) {
+                Some((_, checked_at)) => *checked_at = (*checked_at).min(p.checked_at),
+                None => threads.push((thread, p.checked_at)),
+            }
+        }
+        // Stable, ties keep the notification order
+        threads.sort_by_key(|(_, checked_at)| *checked_at);
+
+        threads.into_iter().take(max).map(|(t, _)| t).collect()
+    }
+
+    pub fn mark_checked(&mut self, channel_id: &ChannelId, thread_ts: &str, now: u64) {
+        for p in self.events.iter_mut() {
+            if p.event.channel_id == *channel_id && thread_of(&p.event) == thread_ts {
+                p.checked_at = now;
+            }
+        }
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.events.iter().map(|p| p.event.id()).collect()
+    }
+
+    // Keeps what other processes acknowledged since `loaded`, the ids read at the start of the cycle,
+    // given `latest`, the file as it is now.
+    pub fn merge(&mut self, loaded: &[String], latest: PendingEvents) {
+        let latest_ids = latest.ids();
+        self.events
+            .retain(|p| latest_ids.contains(&p.event.id()) || !loaded.contains(&p.event.id()));
+        for p in latest.events {
+            let id = p.event.id();
+            if !loaded.contains(&id) && !self.events.iter().any(|e| e.event.id() == id) {
+                self.events.push(p);
+            }
+        }
+    }
+
+    // Events of the thread that me answered, by replying after them or reacting to them.
+    pub fn ack_answered(
+        &mut self,
+        channel_id: &ChannelId,
+        thread: &[MessageNormal],
+        me: &UserId,
+        ack_reactions: &[String],
+    ) -> usize {
+        let reacted = |ts: &str| {
+            thread.iter().any(|m| {
+                m.ts == ts
+                    && m.reactions
+                        .iter()
+                        .flatten()
+                        .any(|r| ack_reactions.contains(&r.name) && r.users.contains(me))
+            })
+        };
+        let replied_after = |ts: &str| {
+            let at = Message::parse_ts(ts);
+            thread.iter().any(|m| {
+                m.user.as_ref() == Some(me) && m.ts != ts && Message::parse_ts(&m.ts) >= at
+            })
+        };
+
+        self.remove(|e| {
+            let in_thread = thread
+                .first()
+                .map(|parent| parent.ts == *e.thread_ts.as_ref().unwrap_or(&e.ts))
+                .unwrap_or(false);
+            e.channel_id == *channel_id && in_thread && (reacted(&e.ts) || replied_after(&e.ts))
+        })
+    }
+
+    fn remove(&mut self, acknowledged: impl Fn(&NotifyEvent) -> bool) -> usize {
+        let before = self.events.len();
+        self.events.retain(|p| !acknowledged(&p.event));
+        before - self.events.len()
+    }
+
+    // What the notifiers' state is made of.
+    pub fn notify_events(&self) -> Vec<&NotifyEvent> {
+        self.events.iter().map(|p| &p.event).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conversation::entity::notification::{NotifyState, Priority};
+
+    fn state(pending: &PendingEvents) -> NotifyState {
+        NotifyState::from_events(&pending.notify_events(), 0)
+    }
+
+    #[test]
+    fn acknowledges_by_id_message_and_channel() {
+        let mut other_channel = NotifyEvent::sample("1720428800.000200", Priority::Low);
+        other_channel.channel_id = "C0000000002".parse().unwrap();
+        other_channel.channel_name = "random".into();
+        let mut pending = PendingEvents::default();
+        pending.add(
+            &[
+                NotifyEvent::sample("1720428655.000200", Priority::Urgent),
+                NotifyEvent::sample("1720428700.000200", Priority::Normal),
+                other_channel,
+            ],
+            1000,
+        );
+        // Notified again, still once
+        pending.add(
+            &[NotifyEvent::sample("1720428655.000200", Priority::Urgent)],
+            1100,
+        );
+        assert_eq!(pending.events().len(), 3);
+        assert_eq!(state(&pending).priority, Some(Priority::Urgent));
+
+        assert_eq!(
+            pending.ack("C07BSNU3GG1:1720428655.000200:DirectMention"),
+            1
+        );
+        assert_eq!(pending.ack("1720428700.000200"), 1);
+        assert_eq!(pending.ack("1720428700.000200"), 0);
+        assert_eq!(state(&pending).priority, Some(Priority::Low));
+
+        assert_eq!(pending.ack_channel("#random"), 1);
+        assert!(!state(&pending).pending);
+    }
+
+    fn reply(user: &str, ts: &str, reactions: &str) -> MessageNormal {
+        serde_json::from_str(&format!(
+            "{{\"user\": \"{}\", \"type\": \"message\", \"ts\": \"{}\", \"text\": \"\", \"reactions\": [{}]}}",
+            user, ts, reactions
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn acknowledges_what_i_answered() {
+        let general: ChannelId = "C07BSNU3GG1".parse().unwrap();
+        let me: UserId = "U0000000ME1".parse().unwrap();
+        let ack_reactions = vec!["eyes".to_string()];
+        let mut mention = NotifyEvent::sample("1720428700.000200", Priority::Urgent);
+        mention.thread_ts = Some("1720428655.000200".into());
+        let mut pending = PendingEvents::default();
+        pending.add(&[mention], 1000);
+        assert_eq!(
+            pending.threads(10),
+            vec![(general.clone(), "1720428655.000200".to_string())]
+        );
+
+        // Replies before the mention, other people's reactions and other emojis do not count
+        let thread = vec![
+            reply("U0000000002", "1720428655.000200", ""),
+            reply("U0000000ME1", "1720428690.000200", ""),
+            reply(
+                "U04853SN1AP",
+                "1720428700.000200",
+                r#"{"name": "eyes", "users": ["U0000000002"], "count": 1},
+                   {"name": "heart", "users": ["U0000000ME1"], "count": 1}"#,
+            ),
+        ];
+        assert_eq!(
+            pending.ack_answered(&general, &thread, &me, &ack_reactions),
+            0
+        );
+
+        let mut reacted = thread.clone();
+        reacted[2] = reply(
+            "U04853SN1AP",
+            "1720428700.000200",
+            r#"{"name": "eyes", "users": ["U0000000ME1"], "count": 1}"#,
+        );
+        assert_eq!(
+            pending
+                .clone()
+                .ack_answered(&general, &reacted, &me, &ack_reactions),
+            1
+        );
+
+        let mut replied = thread.clone();
+        replied.push(reply("U0000000ME1", "1720428720.000200", ""));
+        assert_eq!(
+            pending.ack_answered(&general, &replied, &me, &ack_reactions),
+            1
+        );
+    }
+
+    #[test]
+    fn reads_the_longest_unchecked_threads_first() {
+        let mut pending = PendingEvents::default();
+        pending.add(
+            &[
+                NotifyEvent::sample("1720428655.000200", Priority::Urgent),
+                NotifyEvent::sample("1720428700.000200", Priority::Normal),
+                NotifyEvent::sample("1720428800.000200", Priority::Low),
+            ],
+            1000,
+        );
+
+        let first = pending.threads(2);
+        assert_eq!(first.len(), 2);
+        for (channel_id, thread_ts) in first.iter() {
+            pending.mark_checked(channel_id, thread_ts, 2000);
+        }
+        assert_eq!(
+            pending.threads(2)[0],
+            (
+                "C07BSNU3GG1".parse().unwrap(),
+                "1720428800.000200".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_acknowledgements_made_meanwhile() {
+        let mut pending = PendingEvents::default();
+        pending.add(
+            &[
+                NotifyEvent::sample("1720428655.000200", Priority::Urgent),
+                NotifyEvent::sample("1720428700.000200", Priority::Normal),
+            ],
+            1000,
+        );
+        let loaded = pending.ids();
+
+        // Another process acknowledges the first while the cycle adds a new one
+        let mut latest = pending.clone();
+        latest.ack("1720428655.000200");
+        pending.add(
+            &[NotifyEvent::sample("1720428800.000200", Priority::Low)],
+            1100,
+        );
+
+        pending.merge(&loaded, latest);
+        let ts: Vec<&str> = pending
+            .events()
+            .iter()
+            .map(|p| p.event.ts.as_str())
+            .collect();
+        assert_eq!(ts, vec!["1720428700.000200", "1720428800.000200"]);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut pending = PendingEvents::default();
+        let mut event = NotifyEvent::sample("1720428655.000200", Priority::Urgent);
+        event.thread_ts = Some("1720428600.000100".into());
+        pending.add(&[event], 1000);
+
+        let json = serde_json::to_string(&pending).unwrap();
+        let read: PendingEvents = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.events()[0].event.id(), pending.events()[0].event.id());
+        assert_eq!(read.events()[0].notified_at, 1000);
+        assert_eq!(
+            read.events()[0].event.thread_ts.as_deref(),
+            Some("1720428600.000100")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::config;
+use crate::conversation::ids_str::TeamId;
+use crate::conversation::services::{chat_permalink::get_permalink, slack_probe::get_auth_test};
+
+use super::notification::NotifyEvent;
+
+// Where the workspace lives, to point at its messages.
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    // ie. https://acme.slack.com/
+    pub url: Option<String>,
+    pub team_id: Option<TeamId>,
+}
+
+impl Workspace {
+    pub fn new(url: Option<String>, team_id: Option<TeamId>) -> Workspace {
+        Workspace {
+            url: url.map(|u| format!("{}/", u.trim_end_matches('/'))),
+            team_id,
+        }
+    }
+
+    // As told by auth.test, else notify.team_url and notify.team_id.
+    pub async fn load() -> Workspace {
+        let notify = &config::get().notify;
+        let (url, team_id) = match get_auth_test().await {
+            Ok((auth, _)) if auth.ok => (auth.url, auth.team_id),
+            _ => (None, None),
+        };
+
+        Workspace::new(
+            url.or(notify.team_url.clone()),
+            team_id.or(notify.team_id.clone()),
+        )
+    }
+
+    // The web link, built from the workspace url without asking slack.
+    pub fn permalink(&self, channel_id: &str, ts: &str, thread_ts: Option<&str>) -> Option<String> {
+        let url = self.url.as_ref()?;
+        let mut link = format!("{}archives/{}/p{}", url, channel_id, ts.replace('.', ""));
+        if let Some(thread_ts) = thread_ts.filter(|t| *t != ts) {
+            link.push_str(&format!("?thread_ts={}&cid={}", thread_ts, channel_id));
+        }
+
+        Some(link)
+    }
+
+    // Opens the message in the desktop app.
+    pub fn deep_link(&self, channel_id: &str, ts: &str, thread_ts: Option<&str>) -> Option<String> {
+        let team_id = self.team_id.as_ref()?;
+        let mut link = format!(
+            "slack://channel?team={}&id={}&message={}",
+            team_id, channel_id, ts
+        );
+        if let Some(thread_ts) = thread_ts.filter(|t| *t != ts) {
+            link.push_str(&format!("&thread_ts={}", thread_ts));
+        }
+
+        Some(link)
+    }
+
+    // Fills the links of the events without one, chat.getPermalink first.
+    pub async fn link(&self, events: &mut [NotifyEvent]) {
+        for event in events.iter_mut().filter(|e| e.permalink.is_none()) {
+            let thread_ts = event.thread_ts.as_deref();
+            let asked = match get_permalink(&event.channel_id, &event.ts).await {
+                Ok(response) if response.ok => response.permalink,
+                Ok(response) => {
+                    eprintln!(
+                        "\x1b[93mchat.getPermalink failed. {}\x1b[0m",
+                        response.error.unwrap_or_default()
+                    );
+                    None
+                }
+                Err(_) => None,
+            };
+
+            event.permalink =
+                asked.or_else(|| self.permalink(&event.channel_id, &event.ts, thread_ts));
+            event.deep_link = self.deep_link(&event.channel_id, &event.ts, thread_ts);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builds_the_links() {
+        let workspace = Workspace::new(
+            Some("https://acme.slack.com".into()),
+            TeamId::parse("T0279E2GQPQ").ok(),
+        );
+
+        assert_eq!(
+            workspace.permalink("C07BSNU3GG1", "1720428655.000200", None),
+            Some("https://acme.slack.com/archives/C07BSNU3GG1/p1720428655000200".into())
+        );
+        assert_eq!(
+            workspace.permalink("C07BSNU3GG1", "1720428700.000200", Some("1720428655.000200")),
+            Some("https://acme.slack.com/archives/C07BSNU3GG1/p1720428700000200?thread_ts=1720428655.000200&cid=C07BSNU3GG1".into())
+        );
+        assert_eq!(
+            workspace.deep_link("C07BSNU3GG1", "1720428700.000200", Some("1720428655.000200")),
+            Some("slack://channel?team=T0279E2GQPQ&id=C07BSNU3GG1&message=1720428700.000200&thread_ts=1720428655.000200".into())
+        );
+
+        let offline = Workspace::default();
+        assert_eq!(
+            offline.permalink("C07BSNU3GG1", "1720428655.000200", None),
+            None
+        );
+        assert_eq!(
+            offline.deep_link("C07BSNU3GG1", "1720428655.000200", None),
+            None
+        );
+    }
+}
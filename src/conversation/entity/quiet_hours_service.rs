@@ -0,0 +1,287 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Local, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::config::{self, QuietAction, QuietHoursConfig, QuietRangeConfig};
+use crate::conversation::errors_str::ConfigError;
+use crate::conversation::services::dnd_info::get_dnd_info;
+
+use super::escalation_service::Escalation;
+use super::notification::{NotifyEvent, Priority};
+
+#[derive(Debug, Clone)]
+struct QuietRange {
+    // Every day when empty
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietRange {
+    fn compile(range: &QuietRangeConfig, key: &str) -> Result<QuietRange, ConfigError> {
+        let time = |value: &str, field: &str| match NaiveTime::parse_from_str(value, "%H:%M") {
+            Ok(t) => Ok(t),
+            Err(_) => Err(ConfigError::new(&format!(
+                "{}.{}: {:?} is not a HH:MM time",
+                key, field, value
+            ))),
+        };
+
+        let mut days = Vec::new();
+        for day in range.days.iter() {
+            match Weekday::from_str(day) {
+                Ok(d) => days.push(d),
+                Err(_) => {
+                    return Err(ConfigError::new(&format!(
+                        "{}.days: {:?} is not a week day",
+                        key, day
+                    )))
+                }
+            }
+        }
+
+        Ok(QuietRange {
+            days,
+            start: time(&range.start, "start")?,
+            end: time(&range.end, "end")?,
+        })
+    }
+
+    fn on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn contains(&self, day: Weekday, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            return self.on(day) && self.start <= time && time < self.end;
+        }
+
+        // Crosses midnight, the tail belongs to the previous day's range
+        (self.on(day) && time >= self.start) || (self.on(day.pred()) && time < self.end)
+    }
+}
+
+// The quiet hours schedule from the configuration.
+#[derive(Debug, Clone)]
+pub struct QuietHours {
+    timezone: Option<Tz>,
+    ranges: Vec<QuietRange>,
+    action: QuietAction,
+    urgent_breaks_through: bool,
+    honour_slack_dnd: bool,
+}
+
+impl QuietHours {
+    pub fn compile(config: &QuietHoursConfig) -> Result<QuietHours, ConfigError> {
+        let timezone = match &config.timezone {
+            Some(name) => match Tz::from_str(name) {
+                Ok(tz) => Some(tz),
+                Err(_) => {
+                    return Err(ConfigError::new(&format!(
+                        "quiet_hours.timezone: unknown time zone {}",
+                        name
+                    )))
+                }
+            },
+            None => None,
+        };
+        let ranges = config
+            .ranges
+            .iter()
+            .enumerate()
+            .map(|(i, r)| QuietRange::compile(r, &format!("quiet_hours.ranges[{}]", i)))
+            .collect::<Result<Vec<QuietRange>, ConfigError>>()?;
+
+        Ok(QuietHours {
+            timezone,
+            ranges,
+            action: config.action,
+            urgent_breaks_through: config.urgent_breaks_through,
+            honour_slack_dnd: config.honour_slack_dnd,
+        })
+    }
+
+    // The configuration was validated on load, so it compiles.
+    pub fn from_config() -> QuietHours {
+        match QuietHours::compile(&config::get().quiet_hours) {
+            Ok(q) => q,
+            Err(_) => QuietHours::compile(&QuietHoursConfig::default()).unwrap(),
+        }
+    }
+
+    pub fn in_schedule(&self, at: DateTime<Utc>) -> bool {
+        let (day, time) = match self.timezone {
+            Some(tz) => {
+                let local = at.with_timezone(&tz);
+                (local.weekday(), local.time())
+            }
+            None => {
+                let local = at.with_timezone(&Local);
+                (local.weekday(), local.time())
+            }
+        };
+
+        self.ranges.iter().any(|r| r.contains(day, time))
+    }
+
+    // The schedule, then slack's do not disturb when honoured.
+    pub async fn is_quiet(&self) -> bool {
+        let now = Utc::now();
+        if self.in_schedule(now) {
+            return true;
+        }
+        if !self.honour_slack_dnd {
+            return false;
+        }
+
+        match get_dnd_info().await {
+            Ok(info) if info.ok => info.is_active(now.timestamp()),
+            Ok(info) => {
+                eprintln!(
+                    "\x1b[93mError reading do not disturb {}\x1b[0m",
+                    info.error.unwrap_or_default()
+                );
+                false
+            }
+            Err(e) => {
+                eprintln!("\x1b[93mError reading do not disturb {:?}\x1b[0m", e);
+                false
+            }
+        }
+    }
+
+    // The event to send during quiet time, if any.
+    fn hold(&self, mut event: NotifyEvent) -> Option<NotifyEvent> {
+        if self.urgent_breaks_through && event.priority == Priority::Urgent {
+            return Some(event);
+        }
+        match self.action {
+            QuietAction::Suppress => None,
+            QuietAction::Downgrade => {
+                event.priority = match event.priority {
+                    Priority::Urgent => Priority::Normal,
+                    _ => Priority::Low,
+                };
+                Some(event)
+            }
+        }
+    }
+
+    // Events to send during quiet time, and how many were held back.
+    pub fn apply(&self, events: Vec<NotifyEvent>) -> (Vec<NotifyEvent>, usize) {
+        let total = events.len();
+        let kept: Vec<NotifyEvent> = events.into_iter().filter_map(|e| self.hold(e)).collect();
+        let held = total - kept.len();
+
+        (kept, held)
+    }
+
+    // Same for escalations, which keep the notifiers of their step.
+    pub fn apply_escalations(&self, escalated: Vec<Escalation>) -> (Vec<Escalation>, usize) {
+        let total = escalated.len();
+        let kept: Vec<Escalation> = escalated
+            .into_iter()
+            .filter_map(|e| {
+                self.hold(e.event).map(|event| Escalation {
+                    event,
+                    notifiers: e.notifiers,
+                })
+            })
+            .collect();
+        let held = total - kept.len();
+
+        (kept, held)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn quiet_hours(action: QuietAction) -> QuietHours {
+        QuietHours::compile(&QuietHoursConfig {
+            timezone: Some("Europe/Madrid".into()),
+            ranges: vec![
+                QuietRangeConfig {
+                    days: vec![
+                        "mon".into(),
+                        "tue".into(),
+                        "wed".into(),
+                        "thu".into(),
+                        "fri".into(),
+                    ],
+                    start: "22:00".into(),
+                    end: "07:30".into(),
+                },
+                QuietRangeConfig {
+                    days: vec!["sat".into(), "sun".into()],
+                    start: "00:00".into(),
+                    end: "23:59".into(),
+                },
+            ],
+            action,
+            ..QuietHoursConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn follows_the_schedule_in_its_time_zone() {
+        let quiet = quiet_hours(QuietAction::Suppress);
+        // Madrid is UTC+2 in July
+        let at = |d: u32, h: u32, m: u32| Utc.with_ymd_and_hms(2024, 7, d, h, m, 0).unwrap();
+
+        // Monday 23:30 and Tuesday 07:00 in Madrid
+        assert!(quiet.in_schedule(at(8, 21, 30)));
+        assert!(quiet.in_schedule(at(9, 5, 0)));
+        // Tuesday 08:00 and 21:59
+        assert!(!quiet.in_schedule(at(9, 6, 0)));
+        assert!(!quiet.in_schedule(at(9, 19, 59)));
+        // Saturday noon. Monday 07:00 is not quiet, no weekday range starts on Sunday night
+        assert!(quiet.in_schedule(at(6, 10, 0)));
+        assert!(!quiet.in_schedule(at(8, 5, 0)));
+    }
+
+    #[test]
+    fn suppresses_or_downgrades_but_lets_urgent_through() {
+        let events = || {
+            vec![
+                NotifyEvent::sample("1", Priority::Urgent),
+                NotifyEvent::sample("2", Priority::Normal),
+                NotifyEvent::sample("3", Priority::Low),
+            ]
+        };
+
+        let (kept, held) = quiet_hours(QuietAction::Suppress).apply(events());
+        assert_eq!((kept.len(), held), (1, 2));
+        assert_eq!(kept[0].ts, "1");
+
+        let (kept, held) = quiet_hours(QuietAction::Downgrade).apply(events());
+        assert_eq!(held, 0);
+        let priorities: Vec<Priority> = kept.iter().map(|e| e.priority).collect();
+        assert_eq!(
+            priorities,
+            vec![Priority::Urgent, Priority::Low, Priority::Low]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        let invalid = QuietHours::compile(&QuietHoursConfig {
+            ranges: vec![QuietRangeConfig {
+                days: vec!["someday".into()],
+                start: "22:00".into(),
+                end: "07:00".into(),
+            }],
+            ..QuietHoursConfig::default()
+        });
+
+        assert!(invalid
+            .unwrap_err()
+            .to_string()
+            .starts_with("quiet_hours.ranges[0].days"));
+    }
+}
@@ -0,0 +1,196 @@
+use regex::Regex;
+
+use crate::config::{self, RuleConfig};
+use crate::conversation::errors_str::ConfigError;
+
+// A notification rule from the configuration, with its expressions compiled.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    // Keywords and patterns, any of them fires the rule
+    matchers: Vec<Regex>,
+    // Channel ids or names the rule is scoped to
+    channels: Vec<String>,
+    channel_pattern: Option<Regex>,
+}
+
+impl Rule {
+    pub fn compile(rule: &RuleConfig) -> Result<Rule, ConfigError> {
+        let flags = if rule.case_insensitive { "(?i)" } else { "" };
+        let wrap = |expression: &str| {
+            if rule.whole_word {
+                format!(r"{}\b(?:{})\b", flags, expression)
+            } else {
+                format!("{}{}", flags, expression)
+            }
+        };
+
+        let mut matchers = Vec::new();
+        for keyword in rule.keywords.iter() {
+            matchers.push(Rule::regex(&wrap(&regex::escape(keyword)), &rule.name)?);
+        }
+        for pattern in rule.patterns.iter() {
+            matchers.push(Rule::regex(&wrap(pattern), &rule.name)?);
+        }
+
+        let channel_pattern = match &rule.channel_pattern {
+            Some(p) => Some(Rule::regex(p, &rule.name)?),
+            None => None,
+        };
+
+        Ok(Rule {
+            name: rule.name.clone(),
+            matchers,
+            channels: rule.channels.clone(),
+            channel_pattern,
+        })
+    }
+
+    fn regex(expression: &str, rule_name: &str) -> Result<Regex, ConfigError> {
+        match Regex::new(expression) {
+            Ok(r) => Ok(r),
+            Err(e) => Err(ConfigError::new(&format!(
+                "invalid expression {} in {}. {}",
+                expression, rule_name, e
+            ))),
+        }
+    }
+
+    // Rules without channels nor channel_pattern apply everywhere.
+    pub fn applies_to(&self, channel_id: &str, channel_name: &str) -> bool {
+        if self.channels.is_empty() && self.channel_pattern.is_none() {
+            return true;
+        }
+
+        let listed = self
+            .channels
+            .iter()
+            .any(|c| c == channel_id || c.trim_start_matches('#') == channel_name);
+        let matches_pattern = match &self.channel_pattern {
+            Some(p) => p.is_match(channel_name),
+            None => false,
+        };
+
+        listed || matches_pattern
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        self.matchers.iter().any(|m| m.is_match(text))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn compile(rules: &[RuleConfig]) -> Result<RuleSet, ConfigError> {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                Rule::compile(rule).map_err(|e| ConfigError::new(&format!("rules[{}]: {}", i, e)))
+            })
+            .collect::<Result<Vec<Rule>, ConfigError>>()?;
+
+        Ok(RuleSet { rules })
+    }
+
+    // The configuration was validated on load, so the rules compile.
+    pub fn from_config() -> RuleSet {
+        RuleSet::compile(&config::get().rules).unwrap_or_default()
+    }
+
+    // Names of the rules firing for the text in that channel.
+    pub fn matching(&self, channel_id: &str, channel_name: &str, text: &str) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|r| r.applies_to(channel_id, channel_name) && r.matches(text))
+            .map(|r| r.name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(name: &str) -> RuleConfig {
+        RuleConfig {
+            name: name.into(),
+            ..RuleConfig::default()
+        }
+    }
+
+    #[test]
+    fn matches_keywords_and_patterns() {
+        let rules = RuleSet::compile(&[
+            RuleConfig {
+                keywords: vec!["prod down".into(), "api.v2".into()],
+                ..rule("outage")
+            },
+            RuleConfig {
+                keywords: vec!["nav".into()],
+                whole_word: true,
+                case_insensitive: false,
+                ..rule("team")
+            },
+            RuleConfig {
+                patterns: vec![r"deploy(ed|ing) \w+-service".into()],
+                channel_pattern: Some("^feature-".into()),
+                ..rule("deploys")
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(
+            rules.matching("C1", "general", "PROD DOWN again"),
+            vec!["outage"]
+        );
+        // Keywords are literal text
+        assert!(rules.matching("C1", "general", "apixv2").is_empty());
+        assert_eq!(
+            rules.matching("C1", "general", "ask nav team"),
+            vec!["team"]
+        );
+        assert!(rules.matching("C1", "general", "navigation").is_empty());
+        assert!(rules.matching("C1", "general", "ask NAV team").is_empty());
+        assert_eq!(
+            rules.matching("C2", "feature-navigation", "deployed map-service"),
+            vec!["deploys"]
+        );
+        assert!(rules
+            .matching("C1", "general", "deployed map-service")
+            .is_empty());
+    }
+
+    #[test]
+    fn scopes_by_channel() {
+        let rules = RuleSet::compile(&[RuleConfig {
+            keywords: vec!["release".into()],
+            channels: vec!["#general".into(), "C07BSNU3GG1".into()],
+            ..rule("releases")
+        }])
+        .unwrap();
+
+        assert_eq!(rules.matching("C1", "general", "release"), vec!["releases"]);
+        assert_eq!(
+            rules.matching("C07BSNU3GG1", "feature-navigation", "release"),
+            vec!["releases"]
+        );
+        assert!(rules.matching("C2", "random", "release").is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let invalid = RuleSet::compile(&[RuleConfig {
+            patterns: vec!["(unclosed".into()],
+            ..rule("broken")
+        }]);
+
+        let error = invalid.unwrap_err().to_string();
+        assert!(error.starts_with("rules[0]: invalid expression "));
+        assert!(error.contains("(unclosed in broken."));
+    }
+}
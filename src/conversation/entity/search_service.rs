@@ -0,0 +1,119 @@
+use crate::conversation::errors_str::ArchiveError;
+use crate::conversation::services::archive_db::{Archive, ArchiveFilter, ArchivedMessage};
+
+use super::{
+    channels_service::{Channel, Message},
+    users::User,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    // Words to look for, all of them must be present
+    pub text: Option<String>,
+    // Channel id or name
+    pub channel: Option<String>,
+    // User id or name
+    pub sender: Option<String>,
+    // Seconds, inclusive
+    pub since: Option<i64>,
+    // Seconds, exclusive
+    pub until: Option<i64>,
+    // User id that must be mentioned
+    pub mentions: Option<String>,
+    pub in_thread: bool,
+    pub limit: u32,
+}
+
+impl SearchQuery {
+    // Quotes every word so user input is never read as fts5 syntax.
+    fn match_expression(&self) -> Option<String> {
+        let text = self.text.as_ref()?;
+        let words: Vec<String> = text
+            .split_whitespace()
+            .map(|w| format!("\"{}\"", w.replace('"', "\"\"")))
+            .collect();
+        if words.is_empty() {
+            return None;
+        }
+
+        Some(words.join(" "))
+    }
+
+    fn to_filter(&self) -> ArchiveFilter {
+        ArchiveFilter {
+            text: self.match_expression(),
+            channel: self
+                .channel
+                .as_ref()
+                .map(|c| c.trim_start_matches('#').into()),
+            sender: self.sender.clone(),
+            since: self.since,
+            until: self.until,
+            mentions: self.mentions.clone(),
+            in_thread: self.in_thread,
+            limit: self.limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub channel: Channel,
+    // None for messages without a user, like the bots ones
+    pub author: Option<User>,
+    pub message: Message,
+}
+
+impl From<&ArchivedMessage> for SearchHit {
+    fn from(am: &ArchivedMessage) -> Self {
+        let mut message: Message = (&am.raw).into();
+        message.set_channel_id(&am.channel_id);
+
+        let channel_name = am.channel_name.clone().unwrap_or(am.channel_id.to_string());
+        let author = am.raw.user.clone().map(|author_id| {
+            let author_name = am.user_name.clone().unwrap_or(author_id.to_string());
+            User::new(author_id, &author_name, false)
+        });
+
+        SearchHit {
+            channel: Channel::new(channel_name, am.channel_id.clone(), false),
+            author,
+            message,
+        }
+    }
+}
+
+// Searches the local archive, no slack queries are made.
+pub fn search_messages(query: &SearchQuery) -> Result<Vec<SearchHit>, ArchiveError> {
+    let archive = match Archive::from_config()? {
+        Some(a) => a,
+        None => {
+            return Err(ArchiveError::new(
+                "The message archive is disabled. Set storage.archive to enable it.",
+            ))
+        }
+    };
+
+    let found = archive.search(&query.to_filter())?;
+
+    Ok(found.iter().map(|f| f.into()).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::SearchQuery;
+
+    #[test]
+    fn quotes_search_words() {
+        let query = SearchQuery {
+            text: Some("prod \"down OR".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.match_expression(),
+            Some(String::from("\"prod\" \"\"\"down\" \"OR\""))
+        );
+        assert_eq!(SearchQuery::default().match_expression(), None);
+    }
+}
@@ -1,31 +1,83 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+use crate::conversation::ids_str::UserId;
+use crate::conversation::users_str::SlackUser;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct User {
-    slack_user_id: String,
-    #[allow(dead_code)]
+    slack_user_id: UserId,
     name: String,
     pub should_follow: bool,
+    // Profile details, known once listed by users.list or users.info
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub real_name: Option<String>,
+    // Time zone, ie. Europe/Madrid
+    #[serde(default)]
+    pub tz: Option<String>,
+    #[serde(default)]
+    pub is_bot: bool,
+    #[serde(default)]
+    pub deleted: bool,
+    // Avatar url
+    #[serde(default)]
+    pub avatar: Option<String>,
+}
+
+impl From<&SlackUser> for User {
+    fn from(member: &SlackUser) -> Self {
+        let profile = member.profile.as_ref();
+        User {
+            display_name: profile
+                .and_then(|p| p.display_name.clone())
+                .filter(|n| !n.is_empty()),
+            real_name: member
+                .real_name
+                .clone()
+                .or(profile.and_then(|p| p.real_name.clone()))
+                .filter(|n| !n.is_empty()),
+            tz: member.tz.clone(),
+            is_bot: member.is_bot.unwrap_or(false),
+            deleted: member.deleted.unwrap_or(false),
+            avatar: profile.and_then(|p| p.image_72.clone()),
+            ..User::new(member.id.clone(), &member.display_name(), false)
+        }
+    }
 }
 
 impl User {
-    pub fn new(slack_user_id: &str, name: &str, should_follow: bool) -> User {
+    pub fn new(slack_user_id: UserId, name: &str, should_follow: bool) -> User {
         User {
-            slack_user_id: slack_user_id.into(),
+            slack_user_id,
             name: name.into(),
             should_follow,
+            display_name: None,
+            real_name: None,
+            tz: None,
+            is_bot: false,
+            deleted: false,
+            avatar: None,
         }
     }
 
-    pub fn get_notifyable(users: &Vec<User>) -> Vec<&str> {
+    pub fn id(&self) -> &UserId {
+        &self.slack_user_id
+    }
+
+    // Name as shown in the slack client: display name, real name, else the cached one
+    pub fn name(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .or(self.real_name.as_deref())
+            .unwrap_or(&self.name)
+    }
+
+    pub fn get_notifyable(users: &Vec<User>) -> Vec<&UserId> {
         users
             .iter()
             .filter(|u| u.should_follow)
-            .map(|u| u.slack_user_id.as_str())
+            .map(|u| &u.slack_user_id)
             .collect()
     }
-
-    pub fn ids_intersect(msg_users: &Vec<String>, notify_to: &Vec<&str>) -> bool {
-        let mut user_intersection = msg_users.iter().filter(|u| notify_to.contains(&u.as_str()));
-
-        user_intersection.next().is_some()
-    }
 }
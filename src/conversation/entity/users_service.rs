@@ -1,9 +1,108 @@
-use crate::conversation::services::users_cache_fs;
+use crate::config;
+use crate::conversation::errors_str::{FileSystemError, SlackUserError};
+use crate::conversation::ids_str::UserId;
+use crate::conversation::services::slack_probe::get_auth_test;
+use crate::conversation::services::{users_cache_fs, users_list::get_users_list};
 
-use super::users::User;
+use super::{archive_service, directory_service::UserDirectory, users::User};
+
+// The token's user, notify.user_id when auth.test is out of reach.
+pub async fn my_user_id() -> Option<UserId> {
+    match get_auth_test().await {
+        Ok((auth, _)) if auth.user_id.is_some() => auth.user_id,
+        _ => config::get().notify.user_id.clone(),
+    }
+}
 
 pub fn load_slack_users() -> Vec<User> {
-    let slack_users = users_cache_fs::read_cache();
+    let slack_users = users_cache_fs::read_cache().unwrap_or(Vec::new());
+    archive_service::archive_users(&slack_users);
+
+    slack_users
+}
+
+// Fetches the workspace members and rewrites the cache, keeping who is followed.
+pub async fn refresh_slack_users() -> Result<Vec<User>, SlackUserError> {
+    let cached = users_cache_fs::read_cache().unwrap_or_default();
+    let mut refreshed: Vec<User> = Vec::new();
+    // Bots and deleted users too, their old messages still name them
+    let mut directory = UserDirectory::default();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let page = get_users_list(cursor.as_deref()).await;
+        if let Err(e) = page {
+            return Err(SlackUserError::new(&e.to_string()));
+        }
+        let page = page.unwrap();
+        if !page.ok {
+            return Err(SlackUserError::new(
+                &page.error.unwrap_or("Unknown users error".into()),
+            ));
+        }
+
+        for member in page.members.unwrap_or_default() {
+            let mut user = User::from(&member);
+            user.should_follow = cached
+                .iter()
+                .any(|c| *c.id() == member.id && c.should_follow);
+            directory.insert(user.clone());
+            if user.deleted || user.is_bot {
+                continue;
+            }
+            refreshed.push(user);
+        }
+
+        cursor = page
+            .response_metadata
+            .map(|m| m.next_cursor)
+            .filter(|c| !c.is_empty());
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    // Followed users that are no longer listed are kept
+    for c in cached {
+        if c.should_follow && !refreshed.iter().any(|r| r.id() == c.id()) {
+            refreshed.push(c);
+        }
+    }
+
+    if let Err(e) = users_cache_fs::write_cache(&refreshed) {
+        return Err(SlackUserError::new(&e.to_string()));
+    }
+    if let Err(e) = directory.save() {
+        return Err(SlackUserError::new(&e.to_string()));
+    }
+    archive_service::archive_users(&refreshed);
+
+    Ok(refreshed)
+}
+
+// Follows or unfollows a cached user by id or name. Unknown ids are added.
+pub fn set_follow(user: &str, should_follow: bool) -> Result<User, FileSystemError> {
+    let mut users = users_cache_fs::read_cache().unwrap_or_default();
+
+    let position = users
+        .iter()
+        .position(|u| u.id() == user || u.name().eq_ignore_ascii_case(user));
+    let updated = match position {
+        Some(i) => {
+            users[i].should_follow = should_follow;
+            users[i].clone()
+        }
+        None => match UserId::parse(user) {
+            Ok(user_id) => {
+                let added = User::new(user_id, user, should_follow);
+                users.push(added.clone());
+                added
+            }
+            Err(_) => return Err(FileSystemError::new(&format!("Unknown user {}", user))),
+        },
+    };
+
+    users_cache_fs::write_cache(&users)?;
 
-    slack_users.unwrap_or(Vec::new())
+    Ok(updated)
 }
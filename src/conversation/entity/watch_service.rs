@@ -0,0 +1,543 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::conversation::errors_str::{FileSystemError, SlackChannelError};
+use crate::conversation::ids_str::UserId;
+use crate::conversation::services::state_fs::{read_state, write_state};
+
+use super::{
+    channels_service::{Channel, Message},
+    digest_service::{next_digest, Digest, DigestState},
+    directory_service::UserDirectory,
+    escalation_service::{Escalation, Escalations},
+    ledger_service::NotifyLedger,
+    notification::{NotifyEvent, Priority, Triggers},
+    pending_service::PendingEvents,
+    permalink_service::Workspace,
+    quiet_hours_service::QuietHours,
+    rules_service::RuleSet,
+    users::User,
+    users_service,
+};
+
+// Outcome of one polling cycle.
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleSummary {
+    pub channels_polled: usize,
+    pub new_messages: usize,
+    pub new_replies: usize,
+    pub should_notify: bool,
+    // Highest priority among the events
+    pub priority: Option<Priority>,
+    pub events: Vec<NotifyEvent>,
+    // Within quiet hours or slack's do not disturb
+    pub quiet: bool,
+    // Events held back by the quiet hours
+    pub suppressed: usize,
+    // Events already notified within notify.cooldown_secs
+    pub deduplicated: usize,
+    // Events notified and not acknowledged yet, these included
+    pub pending: usize,
+    // Pending events acknowledged by your replies or reactions
+    pub acknowledged: usize,
+    // Pending events that reached a new escalation step
+    pub escalated: Vec<Escalation>,
+}
+
+// When the watcher last ran a cycle, kept between runs for the catch-up.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct WatchState {
+    // Seconds since epoch, 0 before the first cycle
+    last_run_at: u64,
+}
+
+impl WatchState {
+    fn load() -> Result<WatchState, FileSystemError> {
+        read_state(STATE_FILE)
+    }
+
+    fn save(&self) -> Result<(), FileSystemError> {
+        write_state(STATE_FILE, self)
+    }
+}
+
+static STATE_FILE: &str = "watch_state.json";
+
+// The messages whose replies are followed, kept between runs so `watch --once` sees them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrackedThreads {
+    pub messages: Vec<Message>,
+}
+
+impl TrackedThreads {
+    pub fn load() -> Result<TrackedThreads, FileSystemError> {
+        read_state(THREADS_FILE)
+    }
+
+    fn save(&self) -> Result<(), FileSystemError> {
+        write_state(THREADS_FILE, self)
+    }
+}
+
+static THREADS_FILE: &str = "tracked_threads.json";
+
+pub struct Watcher {
+    channels: Vec<Channel>,
+    users: Vec<User>,
+    // Names for the senders
+    directory: UserDirectory,
+    // Where the links of the events point
+    workspace: Workspace,
+    rules: RuleSet,
+    quiet_hours: QuietHours,
+    // Whose mentions, replies and reactions count
+    my_user_id: Option<UserId>,
+    ledger: NotifyLedger,
+    pending: PendingEvents,
+    escalations: Escalations,
+    // Seconds of the previous cycle
+    last_capture: usize,
+    // Messages whose replies keep being followed, watch.tracked_threads long
+    message_replies: Vec<Option<Message>>,
+    // Print the progress of every cycle
+    verbose: bool,
+}
+
+impl Watcher {
+    pub async fn new(verbose: bool) -> Result<Watcher, SlackChannelError> {
+        let config = config::get();
+        let mut channels = Channel::load_slack_channels().await?;
+        channels.retain(|c| config.channels.selects(&c.channel_id, &c.name));
+        let mut users = users_service::load_slack_users();
+        for user_id in config.notify.users.iter() {
+            match users.iter_mut().find(|u| u.id() == user_id) {
+                Some(u) => u.should_follow = true,
+                None => users.push(User::new(user_id.clone(), user_id, true)),
+            }
+        }
+
+        let my_user_id = users_service::my_user_id().await;
+
+        let mut message_replies = vec![None; config.watch.tracked_threads];
+        match TrackedThreads::load() {
+            Ok(tracked) => {
+                for (slot, message) in message_replies.iter_mut().zip(tracked.messages) {
+                    *slot = Some(message);
+                }
+            }
+            Err(e) => eprintln!("\x1b[93mError reading the tracked threads {}\x1b[0m", e),
+        }
+        // Replies since the previous cycle, as far back as the messages are read
+        let last_capture = match WatchState::load() {
+            Ok(s) if s.last_run_at > 0 => (s.last_run_at as usize)
+                .max(now().saturating_sub(config.history.lookback_secs as usize)),
+            _ => now(),
+        };
+
+        Ok(Watcher {
+            channels,
+            users,
+            directory: UserDirectory::load(),
+            workspace: Workspace::load().await,
+            rules: RuleSet::from_config(),
+            quiet_hours: QuietHours::from_config(),
+            my_user_id,
+            ledger: NotifyLedger::load(),
+            pending: PendingEvents::default(),
+            escalations: Escalations::from_config(),
+            last_capture,
+            message_replies,
+            verbose,
+        })
+    }
+
+    // Who said it, asking users.info for senders the directory does not know.
+    async fn name_senders(&mut self, events: &mut [NotifyEvent]) {
+        let senders: Vec<&UserId> = events.iter().filter_map(|e| e.sender.as_ref()).collect();
+        self.directory.lookup(&senders).await;
+        for event in events.iter_mut() {
+            event.sender_name = event
+                .sender
+                .as_ref()
+                .and_then(|s| self.directory.get(s))
+                .map(|u| u.name().to_string());
+        }
+    }
+
+    fn channel_name(&self, channel_id: &str) -> String {
+        self.channels
+            .iter()
+            .find(|c| c.channel_id == channel_id)
+            .map(|c| c.name.clone())
+            .unwrap_or_default()
+    }
+
+    // Thread replies sent since the previous cycle, oldest first.
+    async fn new_replies(&self, channel_id: &str, thread_ts: &str) -> Vec<Message> {
+        let replies = match Channel::load_thread(channel_id, thread_ts).await {
+            Ok(r) => r,
+            Err(e) => {
+                self.log(&format!("\x1b[93mError loading thread {:?}\x1b[0m", e));
+                return Vec::new();
+            }
+        };
+
+        replies
+            .iter()
+            .filter(|r| r.ts != thread_ts && Message::parse_ts(&r.ts) > self.last_capture)
+            .map(|r| r.into())
+            .collect()
+    }
+
+    fn log(&self, text: &str) {
+        if self.verbose {
+            eprintln!("{}", text);
+        }
+    }
+
+    fn tracked(&self) -> Vec<Message> {
+        self.message_replies.iter().flatten().cloned().collect()
+    }
+
+    // Unacknowledged events as of the last cycle.
+    pub fn pending(&self) -> Vec<&NotifyEvent> {
+        self.pending.notify_events()
+    }
+
+    // The digest of the period since the previous one, when due. The first run only starts counting.
+    pub async fn digest_if_due(&mut self) -> Option<Digest> {
+        let config = &config::get().digest;
+        let now = now() as u64;
+        let mut state = match DigestState::load() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("\x1b[93mError reading the digest state {}\x1b[0m", e);
+                return None;
+            }
+        };
+        if state.last_at == 0 {
+            state.last_at = now;
+            if let Err(e) = state.save() {
+                eprintln!("\x1b[93mError saving the digest state {}\x1b[0m", e);
+            }
+            return None;
+        }
+
+        let last = Local.timestamp_opt(state.last_at as i64, 0).single()?;
+        if next_digest(config, last)?.timestamp() as u64 > now {
+            return None;
+        }
+
+        self.log("Preparing the digest");
+        let digest = Digest::load(
+            "Slack digest",
+            &self.channels,
+            &self.tracked(),
+            state.last_at,
+            now,
+            self.my_user_id.as_ref(),
+        )
+        .await;
+        state.last_at = now;
+        if let Err(e) = state.save() {
+            eprintln!("\x1b[93mError saving the digest state {}\x1b[0m", e);
+        }
+        if let Some(file) = &config.file {
+            if let Err(e) = digest.append_to(file) {
+                eprintln!("\x1b[93m{}\x1b[0m", e);
+            }
+        }
+
+        Some(digest)
+    }
+
+    // What happened since the previous run, when it stopped longer ago than a cycle covers.
+    // Your active threads are followed from then on.
+    pub async fn catch_up(&mut self) -> Option<Digest> {
+        let config = config::get();
+        let now = now() as u64;
+        let last_run_at = match WatchState::load() {
+            Ok(s) => s.last_run_at,
+            Err(e) => {
+                eprintln!("\x1b[93mError reading the watch state {}\x1b[0m", e);
+                return None;
+            }
+        };
+        if !config.watch.catch_up
+            || last_run_at == 0
+            || now.saturating_sub(last_run_at) <= config.history.lookback_secs
+        {
+            return None;
+        }
+
+        let since = last_run_at.max(now.saturating_sub(config.watch.max_catch_up_secs));
+        self.log(&format!("Catching up since {}", since));
+        let report = Digest::load(
+            "While you were away",
+            &self.channels,
+            &self.tracked(),
+            since,
+            now,
+            self.my_user_id.as_ref(),
+        )
+        .await;
+
+        for thread in report.threads.iter() {
+            let tracked = self.message_replies.iter().flatten().any(|m| {
+                m.channel_id.as_ref() == Some(&thread.channel_id)
+                    && m.received_ts == thread.thread_ts
+            });
+            if tracked {
+                continue;
+            }
+            match Channel::load_replies(&thread.channel_id, &thread.thread_ts).await {
+                Ok(Some(mut parent)) => {
+                    parent.set_channel_id(&thread.channel_id);
+                    self.message_replies.rotate_right(1);
+                    self.message_replies[0] = Some(parent);
+                }
+                Ok(None) => {}
+                Err(e) => self.log(&format!("\x1b[93mError loading replies {:?}\x1b[0m", e)),
+            }
+        }
+
+        Some(report)
+    }
+
+    // Reads the threads holding pending events, notify.ack_max_threads of them, dropping those you answered since.
+    async fn auto_ack(&mut self) -> usize {
+        let notify = &config::get().notify;
+        let me = match &self.my_user_id {
+            Some(me) if notify.auto_ack => me.clone(),
+            _ => return 0,
+        };
+
+        let mut acknowledged = 0;
+        for (channel_id, thread_ts) in self.pending.threads(notify.ack_max_threads) {
+            match Channel::load_thread(&channel_id, &thread_ts).await {
+                Ok(thread) => {
+                    self.pending
+                        .mark_checked(&channel_id, &thread_ts, now() as u64);
+                    acknowledged +=
+                        self.pending
+                            .ack_answered(&channel_id, &thread, &me, &notify.ack_reactions)
+                }
+                Err(e) => self.log(&format!("\x1b[93mError loading thread {:?}\x1b[0m", e)),
+            }
+        }
+
+        acknowledged
+    }
+
+    // Loads the new replies of tracked messages and the new channel messages.
+    pub async fn run_cycle(&mut self) -> CycleSummary {
+        self.log(&format!(
+            "-----\n---\nRunning cycle!---\n  last_capture {}.\n---\n",
+            self.last_capture
+        ));
+        let notify = &config::get().notify;
+        let users_sould_notify = User::get_notifyable(&self.users);
+        let triggers = Triggers {
+            my_user_id: self.my_user_id.as_ref(),
+            followed: &users_sould_notify,
+            rules: &self.rules,
+            priorities: &notify.priorities,
+            on_followed: notify.on_messages,
+            on_direct: notify.on_direct_messages,
+        };
+        let mut summary = CycleSummary {
+            channels_polled: 0,
+            new_messages: 0,
+            new_replies: 0,
+            should_notify: false,
+            priority: None,
+            events: Vec::new(),
+            quiet: false,
+            suppressed: 0,
+            deduplicated: 0,
+            pending: 0,
+            acknowledged: 0,
+            escalated: Vec::new(),
+        };
+
+        // Load replies to messages
+        for (i, msg_el) in self.message_replies.to_owned().iter().enumerate() {
+            let message = match msg_el {
+                Some(e) => e,
+                _ => continue,
+            };
+            let Some(channel_id) = message.channel_id.clone() else {
+                continue;
+            };
+
+            let reply_resp = match Channel::load_replies(&channel_id, &message.received_ts).await {
+                Ok(c) => c,
+                Err(e) => {
+                    self.log(&format!("\x1b[93mError loading replies {:?}\x1b[0m", e));
+                    continue;
+                }
+            };
+            let mut reply = match reply_resp {
+                Some(r) => r,
+                _ => continue,
+            };
+
+            reply.channel_id = message.channel_id.to_owned();
+
+            // Should notifiy
+            let has_replies = match &reply.reply {
+                Some(r) => r.latest_reply > self.last_capture,
+                None => false,
+            };
+            if has_replies {
+                let channel_name = self.channel_name(&channel_id);
+                summary.new_replies += 1;
+                self.log(&format!(
+                    "Found new reply on channel_id {} with ts {}",
+                    &channel_id, message.received_ts
+                ));
+                self.log(&format!("users list {:?}", reply.users_list()));
+
+                let mut found = false;
+                for new_reply in self.new_replies(&channel_id, &reply.received_ts).await {
+                    if let Some(event) = triggers.detect(
+                        &channel_id,
+                        &channel_name,
+                        &new_reply,
+                        Some(&reply.received_ts),
+                    ) {
+                        summary.events.push(event);
+                        found = true;
+                    }
+                }
+                if !found && notify.on_thread_replies {
+                    if let Some(event) = triggers.thread_reply(&channel_id, &channel_name, &reply) {
+                        summary.events.push(event);
+                    }
+                }
+            }
+
+            // End by updating the index with the new reply data
+            self.message_replies[i] = Some(reply.to_owned());
+        }
+
+        // Load new messages
+        for s in self.channels.iter() {
+            if s.should_skip {
+                continue;
+            }
+
+            summary.channels_polled += 1;
+            let msg_response = s.load_channel_messages().await;
+            if let Err(e) = msg_response {
+                self.log(&format!(
+                    "\x1b[93mThere was an error loading messages {:?}\x1b[0m",
+                    e
+                ));
+                continue;
+            }
+
+            for mut msg in msg_response.unwrap() {
+                self.log(&format!("Found new message with ts {}", msg.received_at));
+                summary.new_messages += 1;
+                msg.set_channel_id(&s.channel_id);
+
+                if let Some(event) = triggers.detect(&s.channel_id, &s.name, &msg, None) {
+                    summary.events.push(event);
+                }
+
+                self.message_replies.rotate_right(1);
+                self.message_replies[0] = Some(msg);
+            }
+        }
+
+        Message::bubble_sort(&mut self.message_replies);
+        let tracked = TrackedThreads {
+            messages: self.tracked(),
+        };
+        if let Err(e) = tracked.save() {
+            eprintln!("\x1b[93mError saving the tracked threads {}\x1b[0m", e);
+        }
+
+        if !summary.events.is_empty() && self.quiet_hours.is_quiet().await {
+            let (kept, suppressed) = self.quiet_hours.apply(summary.events);
+            self.log(&format!("Quiet time, {} events held back", suppressed));
+            summary.events = kept;
+            summary.quiet = true;
+            summary.suppressed = suppressed;
+        }
+        if !summary.events.is_empty() {
+            let (kept, deduplicated) =
+                self.ledger
+                    .filter(summary.events, now() as u64, notify.cooldown_secs);
+            summary.events = kept;
+            summary.deduplicated = deduplicated;
+            if let Err(e) = self.ledger.save() {
+                eprintln!("\x1b[93mError saving the ledger {}\x1b[0m", e);
+            }
+        }
+        self.name_senders(&mut summary.events).await;
+        self.workspace.link(&mut summary.events).await;
+
+        // Acknowledgements happen in other processes, so the file is read again
+        match PendingEvents::load() {
+            Ok(p) => self.pending = p,
+            Err(e) => eprintln!("\x1b[93mError reading the pending events {}\x1b[0m", e),
+        }
+        let loaded = self.pending.ids();
+        self.pending.add(&summary.events, now() as u64);
+        summary.acknowledged = self.auto_ack().await;
+        // Already answered, no need to tell
+        let still_pending = self.pending.notify_events();
+        summary
+            .events
+            .retain(|e| still_pending.iter().any(|p| p.id() == e.id()));
+        summary.escalated = self.escalations.due(&self.pending, now() as u64);
+        if !summary.escalated.is_empty() && self.quiet_hours.is_quiet().await {
+            let (kept, suppressed) = self.quiet_hours.apply_escalations(summary.escalated);
+            summary.escalated = kept;
+            summary.quiet = true;
+            summary.suppressed += suppressed;
+        }
+        // Held back steps stay due until they are sent
+        self.pending.mark_escalated(&summary.escalated);
+        if !self.pending.events().is_empty() || !loaded.is_empty() {
+            // And again right before saving, auto_ack takes a while
+            match PendingEvents::load() {
+                Ok(latest) => self.pending.merge(&loaded, latest),
+                Err(e) => eprintln!("\x1b[93mError reading the pending events {}\x1b[0m", e),
+            }
+            if let Err(e) = self.pending.save() {
+                eprintln!("\x1b[93mError saving the pending events {}\x1b[0m", e);
+            }
+        }
+        summary.pending = self.pending.events().len();
+
+        let state = WatchState {
+            last_run_at: now() as u64,
+        };
+        if let Err(e) = state.save() {
+            eprintln!("\x1b[93mError saving the watch state {}\x1b[0m", e);
+        }
+
+        summary.priority = summary
+            .events
+            .iter()
+            .chain(summary.escalated.iter().map(|e| &e.event))
+            .map(|e| e.priority)
+            .max();
+        summary.should_notify = !summary.events.is_empty() || !summary.escalated.is_empty();
+        self.last_capture = now();
+        summary
+    }
+}
+
+fn now() -> usize {
+    (match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(n) => n.as_secs(),
+        Err(_) => 0,
+    }) as usize
+}
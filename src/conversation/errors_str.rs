@@ -51,6 +51,31 @@ impl Error for SlackChannelError {
     }
 }
 
+#[derive(Debug)]
+pub struct SlackUserError {
+    details: String,
+}
+
+impl SlackUserError {
+    pub fn new(msg: &str) -> SlackUserError {
+        SlackUserError {
+            details: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SlackUserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for SlackUserError {
+    fn description(&self) -> &str {
+        &self.details
+    }
+}
+
 #[derive(Debug)]
 pub struct FileSystemError {
     details: String,
@@ -75,3 +100,109 @@ impl Error for FileSystemError {
         &self.details
     }
 }
+
+#[derive(Debug)]
+pub struct ArchiveError {
+    details: String,
+}
+
+impl ArchiveError {
+    pub fn new(msg: &str) -> ArchiveError {
+        ArchiveError {
+            details: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for ArchiveError {
+    fn description(&self) -> &str {
+        &self.details
+    }
+}
+
+impl From<rusqlite::Error> for ArchiveError {
+    fn from(e: rusqlite::Error) -> Self {
+        ArchiveError::new(&e.to_string())
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    details: String,
+}
+
+impl ConfigError {
+    pub fn new(msg: &str) -> ConfigError {
+        ConfigError {
+            details: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for ConfigError {
+    fn description(&self) -> &str {
+        &self.details
+    }
+}
+
+#[derive(Debug)]
+pub struct NotifyError {
+    details: String,
+}
+
+impl NotifyError {
+    pub fn new(msg: &str) -> NotifyError {
+        NotifyError {
+            details: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for NotifyError {
+    fn description(&self) -> &str {
+        &self.details
+    }
+}
+
+#[derive(Debug)]
+pub struct IdError {
+    details: String,
+}
+
+impl IdError {
+    pub fn new(msg: &str) -> IdError {
+        IdError {
+            details: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for IdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for IdError {
+    fn description(&self) -> &str {
+        &self.details
+    }
+}
@@ -0,0 +1,215 @@
+use core::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::errors_str::IdError;
+
+// Slack ids are upper case letters and digits behind a one letter kind
+const MIN_LEN: usize = 9;
+
+fn check(id: &str, kind: &str, prefixes: &[char]) -> Result<(), IdError> {
+    let valid = id.starts_with(prefixes)
+        && id.len() >= MIN_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if !valid {
+        return Err(IdError::new(&format!("{} is not a {} id", id, kind)));
+    }
+
+    Ok(())
+}
+
+macro_rules! slack_id {
+    ($(#[$doc:meta])* $name:ident, $kind:literal, $prefixes:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            pub const PREFIXES: &'static [char] = $prefixes;
+
+            pub fn parse(id: &str) -> Result<$name, IdError> {
+                check(id, $kind, Self::PREFIXES)?;
+                Ok($name(id.to_string()))
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = IdError;
+
+            fn from_str(id: &str) -> Result<Self, Self::Err> {
+                $name::parse(id)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = IdError;
+
+            fn try_from(id: String) -> Result<Self, Self::Error> {
+                check(&id, $kind, Self::PREFIXES)?;
+                Ok($name(id))
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> String {
+                id.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+slack_id!(
+    // Public and private channels, group and direct messages
+    ChannelId,
+    "channel",
+    &['C', 'G', 'D']
+);
+slack_id!(
+    // Workspace and Enterprise Grid members
+    UserId,
+    "user",
+    &['U', 'W']
+);
+slack_id!(
+    // Workspaces and Enterprise Grid organizations
+    TeamId,
+    "team",
+    &['T', 'E']
+);
+slack_id!(
+    // User groups, the @handles for several users
+    UserGroupId,
+    "user group",
+    &['S']
+);
+
+// For ids inside bigger payloads, ie. a history page: one odd id is dropped with a warning instead of failing it all.
+pub fn lenient_id<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<String, Error = IdError>,
+{
+    let id = Option::<String>::deserialize(deserializer)?;
+    Ok(id.and_then(keep_valid))
+}
+
+pub fn lenient_ids<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<String, Error = IdError>,
+{
+    let ids = Vec::<String>::deserialize(deserializer)?;
+    Ok(ids.into_iter().filter_map(keep_valid).collect())
+}
+
+pub fn lenient_optional_ids<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<String, Error = IdError>,
+{
+    let ids = Option::<Vec<String>>::deserialize(deserializer)?;
+    Ok(ids.map(|ids| ids.into_iter().filter_map(keep_valid).collect()))
+}
+
+fn keep_valid<T: TryFrom<String, Error = IdError>>(id: String) -> Option<T> {
+    match T::try_from(id) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            eprintln!("\x1b[93mSkipping an id, {}\x1b[0m", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChannelId, UserId};
+    use crate::conversation::messages_str::MessageNormal;
+
+    #[test]
+    fn validates_the_kind() {
+        assert!(ChannelId::parse("C07BSNU3GG1").is_ok());
+        assert!(ChannelId::parse("G07C4B2K0HU").is_ok());
+        assert!(ChannelId::parse("D07C4B2K0HU").is_ok());
+        assert!(UserId::parse("W04853SN1AP").is_ok());
+        assert!(UserId::parse("USLACKBOT").is_ok());
+
+        assert!(ChannelId::parse("U04853SN1AP").is_err());
+        assert!(ChannelId::parse("C1").is_err());
+        assert!(ChannelId::parse("c07bsnu3gg1").is_err());
+        assert_eq!(
+            UserId::parse("general").unwrap_err().to_string(),
+            "general is not a user id"
+        );
+    }
+
+    #[test]
+    fn serializes_as_a_string() {
+        let id: UserId = serde_json::from_str("\"U04853SN1AP\"").unwrap();
+        assert_eq!(id, "U04853SN1AP");
+        assert_eq!(serde_json::to_string(&id).unwrap(), "\"U04853SN1AP\"");
+
+        assert!(serde_json::from_str::<UserId>("\"C07BSNU3GG1\"").is_err());
+    }
+
+    #[test]
+    fn drops_odd_ids_inside_messages() {
+        let message: MessageNormal = serde_json::from_str(
+            r#"{"user": "B01", "type": "message", "ts": "1720428655.000200", "text": "hello",
+                "reply_users": ["U04853SN1AP", "x"],
+                "reactions": [{"name": "eyes", "users": ["", "U0793AZ3ALS"], "count": 2}],
+                "pinned_to": ["C07BSNU3GG1", "general"],
+                "pinned_info": {"channel": "C07BSNU3GG1", "pinned_by": "B02", "pinned_ts": 1720428700}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(message.user, None);
+        assert_eq!(message.reply_users.unwrap(), vec!["U04853SN1AP"]);
+        assert_eq!(message.reactions.unwrap()[0].users, vec!["U0793AZ3ALS"]);
+        assert_eq!(message.pinned_to.unwrap(), vec!["C07BSNU3GG1"]);
+        let pinned = message.pinned_info.unwrap();
+        assert_eq!(pinned.channel.unwrap(), "C07BSNU3GG1");
+        assert_eq!(pinned.pinned_by, None);
+    }
+}
@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+use super::channels_str::PaginationMetadata;
+use super::ids_str::{lenient_id, lenient_ids, lenient_optional_ids, ChannelId, UserId};
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct MessageJoin {
     pub subtype: String,
-    pub user: String,
+    #[serde(default, deserialize_with = "lenient_id")]
+    pub user: Option<UserId>,
     pub text: String,
     pub inviter: String,
     // field: type
@@ -13,7 +17,8 @@ pub struct MessageJoin {
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct MessageNormal {
-    pub user: Option<String>,
+    #[serde(default, deserialize_with = "lenient_id")]
+    pub user: Option<UserId>,
     // field: type
     #[serde(rename = "type")]
     pub message_type: Option<String>,
@@ -28,7 +33,8 @@ pub struct MessageNormal {
     // Message parts
     pub blocks: Option<Vec<BlockInfo>>,
     // Was the message pinned.
-    pub pinned_to: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient_optional_ids")]
+    pub pinned_to: Option<Vec<ChannelId>>,
     pub pinned_info: Option<PinnedInfo>,
     // Emojis
     pub reactions: Option<Vec<Reactions>>,
@@ -40,7 +46,8 @@ pub struct MessageNormal {
     pub reply_count: Option<usize>,
     pub reply_users_count: Option<usize>,
     pub latest_reply: Option<String>,
-    pub reply_users: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient_optional_ids")]
+    pub reply_users: Option<Vec<UserId>>,
     pub thread_ts: Option<String>,
 
     // Bot identifier
@@ -70,15 +77,18 @@ pub struct BlockInfo {
 }
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct PinnedInfo {
-    pub channel: String,
-    pub pinned_by: String,
+    #[serde(default, deserialize_with = "lenient_id")]
+    pub channel: Option<ChannelId>,
+    #[serde(default, deserialize_with = "lenient_id")]
+    pub pinned_by: Option<UserId>,
     pub pinned_ts: usize,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Reactions {
     pub name: String,
-    pub users: Vec<String>,
+    #[serde(deserialize_with = "lenient_ids")]
+    pub users: Vec<UserId>,
     pub count: usize,
 }
 
@@ -95,6 +105,15 @@ pub struct MessageResponse {
     pub channel_actions_ts: Option<f64>,
     pub channel_actions_count: Option<usize>,
     pub warning: Option<String>,
-    // pub response_metadata: Option<HashMap<String, Vec<String>>>,
+    pub response_metadata: Option<PaginationMetadata>,
     pub error: Option<String>, // Option<HashMap<String, Vec<String>>>,
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PermalinkResponse {
+    pub ok: bool,
+    #[serde(default, deserialize_with = "lenient_id")]
+    pub channel: Option<ChannelId>,
+    pub permalink: Option<String>,
+    pub error: Option<String>,
+}
@@ -1,5 +1,7 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::config;
+
 #[allow(dead_code)]
 pub enum METHOD {
     /// The chat history
@@ -14,6 +16,30 @@ pub enum METHOD {
     /// Retrieve a thread of messages posted to a conversation
     /// https://api.slack.com/methods/conversations.replies
     Replies,
+    /// Users
+    /// Lists all users in a Slack team.
+    /// https://api.slack.com/methods/users.list
+    Users,
+    /// User info
+    /// Gets information about a user.
+    /// https://api.slack.com/methods/users.info
+    UserInfo,
+    /// Auth test
+    /// Checks authentication and tells you who you are.
+    /// https://api.slack.com/methods/auth.test
+    AuthTest,
+    /// User groups
+    /// List all User Groups for a team.
+    /// https://api.slack.com/methods/usergroups.list
+    UserGroups,
+    /// Permalink
+    /// Retrieve a permalink URL for a specific extant message.
+    /// https://api.slack.com/methods/chat.getPermalink
+    Permalink,
+    /// Do not disturb
+    /// Retrieves a user's current Do Not Disturb status.
+    /// https://api.slack.com/methods/dnd.info
+    DndInfo,
 }
 
 pub struct ApiMethod {
@@ -32,6 +58,12 @@ pub fn get_method(m: METHOD) -> ApiMethod {
         METHOD::ConversationHistory => new_api_method(String::from("conversations.history"), get),
         METHOD::Channels => new_api_method(String::from("conversations.list"), get),
         METHOD::Replies => new_api_method(String::from("conversations.replies"), get),
+        METHOD::Users => new_api_method(String::from("users.list"), get),
+        METHOD::UserInfo => new_api_method(String::from("users.info"), get),
+        METHOD::AuthTest => new_api_method(String::from("auth.test"), get),
+        METHOD::UserGroups => new_api_method(String::from("usergroups.list"), get),
+        METHOD::Permalink => new_api_method(String::from("chat.getPermalink"), get),
+        METHOD::DndInfo => new_api_method(String::from("dnd.info"), get),
     }
 }
 
@@ -43,6 +75,8 @@ pub struct ChatHistoryOptions {
     next_page: Option<String>,
     // Messages after the given timestap
     messages_since: Option<u64>,
+    // Messages before the given timestamp
+    messages_until: Option<u64>,
 
     // Message id. Often ts
     message_id: Option<String>,
@@ -52,15 +86,17 @@ pub struct ChatHistoryOptions {
 
 impl Default for ChatHistoryOptions {
     fn default() -> Self {
+        let history = &config::get().history;
         let messages_since = match SystemTime::now().duration_since(UNIX_EPOCH) {
-            Ok(n) => Some(n.as_secs() - 300),
+            Ok(n) => Some(n.as_secs().saturating_sub(history.lookback_secs)),
             Err(_) => None,
         };
 
         Self {
-            limit: 10,
+            limit: history.limit,
             next_page: None,
             messages_since,
+            messages_until: None,
             message_id: None,
             channel_id: None,
         }
@@ -76,6 +112,9 @@ impl ChatHistoryOptions {
         if let Some(query) = &self.messages_since {
             query_resp.push_str(format!("&oldest={}.000200", query).as_str());
         }
+        if let Some(query) = &self.messages_until {
+            query_resp.push_str(format!("&latest={}.000000", query).as_str());
+        }
 
         query_resp
     }
@@ -91,6 +130,21 @@ impl ChatHistoryOptions {
         query_resp
     }
 
+    pub fn to_query_replies_args(&self) -> String {
+        let mut query_resp = format!("limit={}", self.limit);
+        if let Some(query) = &self.channel_id {
+            query_resp.push_str(format!("&channel={}", query).as_str());
+        }
+        if let Some(query) = &self.message_id {
+            query_resp.push_str(format!("&ts={}", query).as_str());
+        }
+        if let Some(query) = &self.next_page {
+            query_resp.push_str(format!("&cursor={}", query).as_str());
+        }
+
+        query_resp
+    }
+
     pub fn only_one(&mut self) {
         self.limit = 1;
     }
@@ -99,4 +153,18 @@ impl ChatHistoryOptions {
         self.channel_id = Some(channel_id.into());
         self.message_id = Some(message_id.into());
     }
+
+    // Messages between the two timestamps, in seconds.
+    pub fn set_range(&mut self, since: u64, until: Option<u64>) {
+        self.messages_since = Some(since);
+        self.messages_until = until;
+    }
+
+    pub fn set_limit(&mut self, limit: u32) {
+        self.limit = limit.min(1000);
+    }
+
+    pub fn set_next_page(&mut self, cursor: Option<String>) {
+        self.next_page = cursor.filter(|c| !c.is_empty());
+    }
 }
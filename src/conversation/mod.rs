@@ -1,6 +1,10 @@
+pub mod auth_str;
 pub mod channels_str;
+pub mod dnd_str;
 pub mod entity;
 pub mod errors_str;
+pub mod ids_str;
 pub mod messages_str;
 pub mod methods_aggregate;
 pub mod services;
+pub mod users_str;
@@ -0,0 +1,462 @@
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use rusqlite::{params, Connection};
+
+use crate::config;
+use crate::conversation::{
+    errors_str::ArchiveError, ids_str::ChannelId, messages_str::MessageNormal,
+};
+
+static SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS channels (
+    channel_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    ignored INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS users (
+    user_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    should_follow INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS messages (
+    channel_id TEXT NOT NULL,
+    ts TEXT NOT NULL,
+    user_id TEXT,
+    text TEXT NOT NULL,
+    subtype TEXT,
+    thread_ts TEXT,
+    received_at INTEGER NOT NULL,
+    raw TEXT NOT NULL,
+    PRIMARY KEY (channel_id, ts)
+);
+CREATE INDEX IF NOT EXISTS messages_received_at ON messages (received_at);
+CREATE TABLE IF NOT EXISTS threads (
+    channel_id TEXT NOT NULL,
+    thread_ts TEXT NOT NULL,
+    reply_count INTEGER NOT NULL DEFAULT 0,
+    latest_reply TEXT,
+    reply_users TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (channel_id, thread_ts)
+);
+CREATE TABLE IF NOT EXISTS reactions (
+    channel_id TEXT NOT NULL,
+    ts TEXT NOT NULL,
+    name TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    PRIMARY KEY (channel_id, ts, name, user_id)
+);
+";
+
+// Full text index over the message text, kept in sync by triggers.
+static FTS_SCHEMA: &str = "
+CREATE VIRTUAL TABLE messages_fts USING fts5(text, content='messages', content_rowid='rowid');
+CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, text) VALUES (new.rowid, new.text);
+END;
+CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+END;
+CREATE TRIGGER messages_fts_update AFTER UPDATE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+    INSERT INTO messages_fts (rowid, text) VALUES (new.rowid, new.text);
+END;
+INSERT INTO messages_fts (messages_fts) VALUES ('rebuild');
+";
+
+// Filters for `Archive::search`. Unset fields do not filter.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveFilter {
+    // fts5 match expression
+    pub text: Option<String>,
+    // Channel id or name
+    pub channel: Option<String>,
+    // User id or name of the sender
+    pub sender: Option<String>,
+    // Seconds, inclusive
+    pub since: Option<i64>,
+    // Seconds, exclusive
+    pub until: Option<i64>,
+    // User id that must be mentioned in the text
+    pub mentions: Option<String>,
+    // Only messages that belong to a thread
+    pub in_thread: bool,
+    pub limit: u32,
+}
+
+// A stored message with the names of its channel and sender.
+#[derive(Debug, Clone)]
+pub struct ArchivedMessage {
+    pub channel_id: ChannelId,
+    pub channel_name: Option<String>,
+    pub user_name: Option<String>,
+    // The message as received from slack
+    pub raw: MessageNormal,
+}
+
+// Local copy of everything the watcher fetched from slack.
+pub struct Archive {
+    conn: Connection,
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Archive, ArchiveError> {
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                eprintln!("{:?}", e);
+                return Err(ArchiveError::new("Error creating archive folder"));
+            }
+        }
+        Archive::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Archive, ArchiveError> {
+        Archive::init(Connection::open_in_memory()?)
+    }
+
+    // The archive set in storage.archive, if any. Opened once, the process shares the connection.
+    pub fn from_config() -> Result<Option<MutexGuard<'static, Archive>>, ArchiveError> {
+        static SHARED: OnceLock<Mutex<Archive>> = OnceLock::new();
+        let path = match &config::get().storage.archive {
+            Some(path) if !path.as_os_str().is_empty() => path,
+            _ => return Ok(None),
+        };
+
+        let shared = match SHARED.get() {
+            Some(s) => s,
+            None => {
+                let archive = Archive::open(path)?;
+                SHARED.get_or_init(|| Mutex::new(archive))
+            }
+        };
+        // A panic while holding it leaves the connection usable
+        Ok(Some(shared.lock().unwrap_or_else(|e| e.into_inner())))
+    }
+
+    fn init(conn: Connection) -> Result<Archive, ArchiveError> {
+        conn.execute_batch(SCHEMA)?;
+
+        let has_fts: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'messages_fts')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_fts {
+            conn.execute_batch(FTS_SCHEMA)?;
+        }
+
+        Ok(Archive { conn })
+    }
+
+    pub fn save_channel(
+        &self,
+        channel_id: &str,
+        name: &str,
+        ignored: bool,
+    ) -> Result<(), ArchiveError> {
+        self.conn.execute(
+            "INSERT INTO channels (channel_id, name, ignored) VALUES (?1, ?2, ?3)
+             ON CONFLICT (channel_id) DO UPDATE SET name = excluded.name, ignored = excluded.ignored",
+            params![channel_id, name, ignored],
+        )?;
+        Ok(())
+    }
+
+    pub fn save_user(
+        &self,
+        user_id: &str,
+        name: &str,
+        should_follow: bool,
+    ) -> Result<(), ArchiveError> {
+        self.conn.execute(
+            "INSERT INTO users (user_id, name, should_follow) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET name = excluded.name, should_follow = excluded.should_follow",
+            params![user_id, name, should_follow],
+        )?;
+        Ok(())
+    }
+
+    // Stores the raw messages together with their thread and reaction details.
+    pub fn save_messages(
+        &mut self,
+        channel_id: &str,
+        messages: &[MessageNormal],
+    ) -> Result<usize, ArchiveError> {
+        let tx = self.conn.transaction()?;
+        for mn in messages {
+            let raw = match serde_json::to_string(mn) {
+                Ok(r) => r,
+                Err(e) => return Err(ArchiveError::new(&e.to_string())),
+            };
+            let received_at: i64 = mn.ts.split('.').next().unwrap_or("0").parse().unwrap_or(0);
+            tx.execute(
+                "INSERT INTO messages (channel_id, ts, user_id, text, subtype, thread_ts, received_at, raw)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (channel_id, ts) DO UPDATE SET
+                    text = excluded.text, subtype = excluded.subtype,
+                    thread_ts = excluded.thread_ts, raw = excluded.raw",
+                params![channel_id, mn.ts, mn.user.as_deref(), mn.text, mn.subtype, mn.thread_ts, received_at, raw],
+            )?;
+
+            if mn.reply_count.is_some() || mn.latest_reply.is_some() {
+                tx.execute(
+                    "INSERT INTO threads (channel_id, thread_ts, reply_count, latest_reply, reply_users)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT (channel_id, thread_ts) DO UPDATE SET
+                        reply_count = excluded.reply_count, latest_reply = excluded.latest_reply,
+                        reply_users = excluded.reply_users",
+                    params![
+                        channel_id,
+                        mn.ts,
+                        mn.reply_count.unwrap_or(0) as i64,
+                        mn.latest_reply,
+                        mn.reply_users
+                            .iter()
+                            .flatten()
+                            .map(|u| u.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(","),
+                    ],
+                )?;
+            }
+
+            tx.execute(
+                "DELETE FROM reactions WHERE channel_id = ?1 AND ts = ?2",
+                params![channel_id, mn.ts],
+            )?;
+            for reaction in mn.reactions.iter().flatten() {
+                for user in reaction.users.iter() {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO reactions (channel_id, ts, name, user_id) VALUES (?1, ?2, ?3, ?4)",
+                        params![channel_id, mn.ts, reaction.name, user.as_str()],
+                    )?;
+                }
+            }
+        }
+        tx.commit()?;
+
+        Ok(messages.len())
+    }
+
+    // Threads started or replied to by the user, with a reply since `since`. Channel id and thread ts.
+    pub fn involved_threads(
+        &self,
+        user_id: &str,
+        since: u64,
+    ) -> Result<Vec<(ChannelId, String)>, ArchiveError> {
+        let mut statement = self.conn.prepare(
+            "SELECT t.channel_id, t.thread_ts
+             FROM threads t
+             LEFT JOIN messages m ON m.channel_id = t.channel_id AND m.ts = t.thread_ts
+             WHERE CAST(t.latest_reply AS REAL) >= ?2
+               AND (m.user_id = ?1 OR instr(',' || t.reply_users || ',', ',' || ?1 || ',') > 0)
+             ORDER BY t.latest_reply",
+        )?;
+        let rows = statement.query_map(params![user_id, since as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut threads = Vec::new();
+        for row in rows {
+            let (channel_id, thread_ts) = row?;
+            match ChannelId::parse(&channel_id) {
+                Ok(c) => threads.push((c, thread_ts)),
+                Err(e) => eprintln!("Skipping archived thread of an unknown channel. {:?}", e),
+            }
+        }
+        Ok(threads)
+    }
+
+    pub fn search(&self, filter: &ArchiveFilter) -> Result<Vec<ArchivedMessage>, ArchiveError> {
+        let mut statement = self.conn.prepare(
+            "SELECT m.channel_id, c.name, u.name, m.raw
+             FROM messages m
+             LEFT JOIN channels c ON c.channel_id = m.channel_id
+             LEFT JOIN users u ON u.user_id = m.user_id
+             WHERE (?1 IS NULL OR m.rowid IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?1))
+               AND (?2 IS NULL OR m.channel_id = ?2 OR c.name = ?2)
+               AND (?3 IS NULL OR m.user_id = ?3 OR u.name = ?3)
+               AND (?4 IS NULL OR m.received_at >= ?4)
+               AND (?5 IS NULL OR m.received_at < ?5)
+               AND (?6 IS NULL OR instr(m.text, '<@' || ?6) > 0)
+               AND (?7 = 0 OR m.thread_ts IS NOT NULL)
+             ORDER BY m.received_at DESC, m.ts DESC
+             LIMIT ?8",
+        )?;
+
+        let rows = statement.query_map(
+            params![
+                filter.text,
+                filter.channel,
+                filter.sender,
+                filter.since,
+                filter.until,
+                filter.mentions,
+                filter.in_thread,
+                filter.limit,
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )?;
+
+        let mut found = Vec::new();
+        for row in rows {
+            let (channel_id, channel_name, user_name, raw) = row?;
+            let channel_id = match ChannelId::parse(&channel_id) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("Skipping archived message of an unknown channel. {:?}", e);
+                    continue;
+                }
+            };
+            let raw: MessageNormal = match serde_json::from_str(&raw) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Skipping unreadable archived message. {:?}", e);
+                    continue;
+                }
+            };
+            found.push(ArchivedMessage {
+                channel_id,
+                channel_name,
+                user_name,
+                raw,
+            });
+        }
+
+        Ok(found)
+    }
+
+    #[cfg(test)]
+    pub fn message_count(&self, channel_id: &str) -> Result<usize, ArchiveError> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE channel_id = ?1",
+            params![channel_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Archive, ArchiveFilter, ChannelId};
+    use crate::conversation::messages_str::MessageNormal;
+
+    fn thread_message() -> MessageNormal {
+        let serialized = "{
+            \"user\": \"U04853SN1AP\",
+            \"type\": \"message\",
+            \"ts\": \"1720428655.000200\",
+            \"text\": \"Deploy is done <@U0793AZ3ALS>\",
+            \"reply_count\": 2,
+            \"latest_reply\": \"1720428900.000100\",
+            \"reply_users\": [\"U0793AZ3ALS\"],
+            \"reactions\": [{\"name\": \"eyes\", \"users\": [\"U0793AZ3ALS\"], \"count\": 1}]
+        }";
+        serde_json::from_str(serialized).unwrap()
+    }
+
+    #[test]
+    fn stores_messages_and_threads() {
+        let mut archive = Archive::open_in_memory().unwrap();
+        let message = thread_message();
+
+        archive
+            .save_messages("C07BSNU3GG1", std::slice::from_ref(&message))
+            .unwrap();
+        // Saving twice updates in place
+        archive.save_messages("C07BSNU3GG1", &[message]).unwrap();
+
+        assert_eq!(archive.message_count("C07BSNU3GG1").unwrap(), 1);
+
+        // The parent's author and the repliers are in it, until the period passes its latest reply
+        let thread = vec![(
+            ChannelId::parse("C07BSNU3GG1").unwrap(),
+            String::from("1720428655.000200"),
+        )];
+        assert_eq!(
+            archive.involved_threads("U0793AZ3ALS", 1720428800).unwrap(),
+            thread
+        );
+        assert_eq!(
+            archive.involved_threads("U04853SN1AP", 1720428800).unwrap(),
+            thread
+        );
+        assert!(archive
+            .involved_threads("U0000000002", 1720428800)
+            .unwrap()
+            .is_empty());
+        assert!(archive
+            .involved_threads("U0793AZ3ALS", 1720429000)
+            .unwrap()
+            .is_empty());
+    }
+
+    fn search_fixture() -> Archive {
+        let mut archive = Archive::open_in_memory().unwrap();
+        archive
+            .save_channel("C07BSNU3GG1", "feature-navigation", false)
+            .unwrap();
+        archive.save_user("U04853SN1AP", "Ana", true).unwrap();
+
+        let mut reply = thread_message();
+        reply.ts = "1720429000.000100".into();
+        reply.thread_ts = Some("1720428655.000200".into());
+        reply.text = "Rollback of the navigation service".into();
+        reply.user = Some("U0793AZ3ALS".parse().unwrap());
+
+        archive
+            .save_messages("C07BSNU3GG1", &[thread_message(), reply])
+            .unwrap();
+        archive
+    }
+
+    #[test]
+    fn searches_text_and_filters() {
+        let archive = search_fixture();
+        let all = ArchiveFilter {
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(archive.search(&all).unwrap().len(), 2);
+
+        let by_text = ArchiveFilter {
+            text: Some("\"deploy\"".into()),
+            ..all.clone()
+        };
+        let found = archive.search(&by_text).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].channel_name, Some("feature-navigation".into()));
+        assert_eq!(found[0].user_name, Some("Ana".into()));
+
+        let by_sender = ArchiveFilter {
+            sender: Some("Ana".into()),
+            ..all.clone()
+        };
+        assert_eq!(archive.search(&by_sender).unwrap().len(), 1);
+
+        let mentions = ArchiveFilter {
+            mentions: Some("U0793AZ3ALS".into()),
+            ..all.clone()
+        };
+        assert_eq!(
+            archive.search(&mentions).unwrap()[0].raw.ts,
+            "1720428655.000200"
+        );
+
+        let in_thread = ArchiveFilter {
+            in_thread: true,
+            until: Some(1720429000),
+            ..all
+        };
+        assert_eq!(archive.search(&in_thread).unwrap().len(), 0);
+    }
+}
@@ -1,14 +1,29 @@
+use crate::config::{self, ConversationType};
 use crate::conversation::errors_str::FileSystemError;
+use crate::conversation::ids_str::ChannelId;
+use crate::conversation::services::state_fs::{read_state, write_state};
+use crate::conversation::services::storage_path::{
+    check_csv, csv_field, split_csv, storage_dir, storage_file, CacheCheck,
+};
+use serde::{Deserialize, Serialize};
 use std::fs::{self, OpenOptions};
 use std::io::{BufRead, BufReader};
 use std::{fs::File, io::Write, path::Path};
 
-static FILE_PATH: &'static str = "static/storage";
-static FILE_NAME: &'static str = "static/storage/channels_cache.txt";
+static FILE_NAME: &str = "channels_cache.txt";
+static FILE_HEADER: &str = "channel_id,channel-name,added_manually,should_ignore";
+static TYPES_FILE: &str = "channels_cache_types.json";
 
+// The channels.types the cache was listed with.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheTypes {
+    types: Vec<ConversationType>,
+}
+
+#[derive(Debug, Clone)]
 pub struct ChannelStorage {
     // Slack unique id
-    pub channel_id: String,
+    pub channel_id: ChannelId,
     // Channel name
     pub name: String,
     // Was it manually inserted
@@ -17,8 +32,14 @@ pub struct ChannelStorage {
     pub ignore: bool,
 }
 
-fn echo(s: &str, path: &Path) -> Result<(), FileSystemError> {
-    let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
+fn echo(s: &str, path: &Path, overwrite: bool) -> Result<(), FileSystemError> {
+    let mut options = OpenOptions::new();
+    if overwrite {
+        options.write(true).create(true).truncate(true);
+    } else {
+        options.write(true).create_new(true);
+    }
+    let mut file = match options.open(path) {
         Ok(f) => f,
         _ => return Err(FileSystemError::new("Failed opening the object path.")),
     };
@@ -29,45 +50,77 @@ fn echo(s: &str, path: &Path) -> Result<(), FileSystemError> {
     // };
 
     if let Err(e) = file.write_all(s.as_bytes()) {
-        println!("{:?}", e);
+        eprintln!("{:?}", e);
         return Err(FileSystemError::new("Failed to write file contents."));
     }
 
     return Ok(());
 }
 
+fn as_lines(storage: &[ChannelStorage]) -> Vec<String> {
+    let mut lines = vec![FILE_HEADER.to_string()];
+    lines.extend(storage.iter().map(|s| {
+        format!(
+            "{},{},{},{}",
+            s.channel_id,
+            csv_field(&s.name),
+            s.custom,
+            s.ignore
+        )
+    }));
+    lines
+}
+
 pub fn create_cache(storage: &Vec<ChannelStorage>) -> Result<(), FileSystemError> {
-    let as_lines: Vec<String> = storage
-        .iter()
-        .map(|s| format!("{},{},{}", s.channel_id, s.name, s.custom))
-        .collect();
+    let as_lines = as_lines(storage);
     let line_items: Vec<&str> = as_lines.iter().map(|f| f.as_str()).collect();
-    if let Err(e) = store_cache(line_items) {
-        println!("{:?}", e);
+    if let Err(e) = store_cache(line_items, false) {
+        eprintln!("{:?}", e);
         return Err(FileSystemError::new("Failed to create file cache."));
     }
 
     Ok(())
 }
-fn store_cache(lines: Vec<&str>) -> Result<(), FileSystemError> {
+
+// Replaces the cache file contents.
+pub fn update_cache(storage: &[ChannelStorage]) -> Result<(), FileSystemError> {
+    let as_lines = as_lines(storage);
+    let line_items: Vec<&str> = as_lines.iter().map(|f| f.as_str()).collect();
+    if let Err(e) = store_cache(line_items, true) {
+        eprintln!("{:?}", e);
+        return Err(FileSystemError::new("Failed to update file cache."));
+    }
+
+    Ok(())
+}
+
+fn store_cache(lines: Vec<&str>, overwrite: bool) -> Result<(), FileSystemError> {
     // mkdir -p static/storage/
-    if let Err(e) = fs::create_dir_all(FILE_PATH) {
-        println!("{:?}", e);
+    if let Err(e) = fs::create_dir_all(storage_dir()) {
+        eprintln!("{:?}", e);
         return Err(FileSystemError::new("Error creating storage folder"));
     }
 
-    println!("echo [content] > static/storage/[filename.ext]");
-    let path = &Path::new(FILE_NAME);
+    let path = storage_file(FILE_NAME);
+    eprintln!("echo [content] > {}", path.display());
     let content = lines.join("\n");
-    echo(&content, path)?;
+    echo(&content, &path, overwrite)?;
 
-    return Ok(());
+    let types = config::get().channels.types.clone();
+    write_state(TYPES_FILE, &CacheTypes { types })
+}
+
+// Empty for caches older than the types file, or unreadable ones.
+pub fn cached_types() -> Vec<ConversationType> {
+    read_state::<CacheTypes>(TYPES_FILE)
+        .map(|c| c.types)
+        .unwrap_or_default()
 }
 
 pub fn read_cache() -> Result<Vec<ChannelStorage>, FileSystemError> {
-    let file = File::open(FILE_NAME);
+    let file = File::open(storage_file(FILE_NAME));
     if let Err(error) = file {
-        println!("{:?}", error);
+        eprintln!("{:?}", error);
         return Err(FileSystemError::new("Error opening file. {:?}"));
     }
     let file = file.unwrap();
@@ -77,23 +130,28 @@ pub fn read_cache() -> Result<Vec<ChannelStorage>, FileSystemError> {
     let reader = BufReader::new(file);
     for line in reader.lines() {
         if let Err(e) = line {
-            println!("eeor {:?}", e);
+            eprintln!("eeor {:?}", e);
             continue;
         }
         let l = line.unwrap();
 
-        let channel_as_line: Vec<&str> = l.split(",").collect();
-        if channel_as_line.is_empty() || !channel_as_line[0].starts_with("C") {
+        let channel_as_line = split_csv(&l);
+        // Skips the header and anything that is not a channel id
+        let Ok(channel_id) = ChannelId::parse(&channel_as_line[0]) else {
             continue;
-        }
+        };
         let channel_storage = ChannelStorage {
-            channel_id: channel_as_line[0].try_into().unwrap_or("".to_string()),
-            name: channel_as_line[1].try_into().unwrap_or("".to_string()),
-            custom: channel_as_line[2] == "true",
-            ignore: channel_as_line[3] == "true",
+            channel_id,
+            name: channel_as_line.get(1).cloned().unwrap_or_default(),
+            custom: channel_as_line.get(2).is_some_and(|f| f == "true"),
+            ignore: channel_as_line.get(3).is_some_and(|f| f == "true"),
         };
         slack_channels.push(channel_storage);
     }
 
     return Ok(slack_channels);
 }
+
+pub fn check_cache() -> Result<CacheCheck, FileSystemError> {
+    check_csv(FILE_NAME, 4, |id| ChannelId::parse(id).is_ok(), &[2, 3])
+}
@@ -1,30 +1,28 @@
+use crate::config::{self, ConversationType};
 use crate::conversation::{
     channels_str::ChannelResponse,
     errors_str::QueryError,
     methods_aggregate::{get_method, METHOD},
+    services::slack_request::slack_headers,
 };
 
 pub async fn get_conversation_channels(
     paginate: Option<&str>,
+    types: &[ConversationType],
 ) -> Result<ChannelResponse, QueryError> {
     let client = reqwest::Client::new();
 
     let slack_method = get_method(METHOD::Channels);
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    let auth_token = std::env::var("SLACK_TOKEN").unwrap();
-    headers.insert(
-        reqwest::header::AUTHORIZATION,
-        format!("Bearer {}", auth_token).parse().unwrap(),
-    );
-    headers.insert(
-        reqwest::header::CONTENT_TYPE,
-        "application/json; charset=utf-8".parse().unwrap(),
-    );
+    let headers = slack_headers();
 
+    let channels = &config::get().channels;
+    let types: Vec<&str> = types.iter().map(|t| t.as_str()).collect();
     let mut url: String = format!(
-        "https://slack.com/api/{}?exclude_archived=true",
-        slack_method.action
+        "https://slack.com/api/{}?exclude_archived={}&types={}",
+        slack_method.action,
+        channels.exclude_archived,
+        types.join(",")
     );
     if let Some(paginate_cursor) = paginate {
         url = format!(
@@ -39,14 +37,14 @@ pub async fn get_conversation_channels(
     let res = client.get(url).headers(headers).send().await;
 
     if let Err(x) = res {
-        println!("{:?}", x);
+        eprintln!("{:?}", x);
         return Err(QueryError::new("Query convert res to response"));
     }
 
     let response = res.unwrap();
     let res_json = response.json::<ChannelResponse>().await;
     if let Err(x) = res_json {
-        println!("{:?}", x);
+        eprintln!("{:?}", x);
         return Err(QueryError::new("Query convert response to json"));
     }
 
@@ -56,11 +54,12 @@ pub async fn get_conversation_channels(
 
 #[cfg(test)]
 mod test {
+    use crate::config;
     use crate::conversation::services::chat_channels::get_conversation_channels;
 
     #[tokio::test]
     async fn loads_the_chat_lists() {
-        let res = get_conversation_channels(None).await;
+        let res = get_conversation_channels(None, &config::get().channels.types).await;
         let fu;
         if let Err(m) = res {
             println!("Error was cought");
@@ -76,7 +75,7 @@ mod test {
 
     #[tokio::test]
     async fn processes_the_channel_lists() {
-        let res = get_conversation_channels(None).await;
+        let res = get_conversation_channels(None, &config::get().channels.types).await;
         let fu;
         if let Err(m) = res {
             println!("Error was cought");
@@ -91,14 +90,18 @@ mod test {
         if let Some(slack_channels) = fu.channels {
             println!("Found {} channels", slack_channels.len());
             for slack_channel in &slack_channels {
-                println!("{} - {}", &slack_channel.id, &slack_channel.name);
+                println!(
+                    "{} - {}",
+                    &slack_channel.id,
+                    slack_channel.name().unwrap_or_default()
+                );
             }
         }
 
         assert!(fu.response_metadata.is_some());
         let more_lists: &str = &fu.response_metadata.unwrap().next_cursor;
 
-        let res = get_conversation_channels(Some(more_lists)).await;
+        let res = get_conversation_channels(Some(more_lists), &config::get().channels.types).await;
         let fu;
         if let Err(m) = res {
             println!("Error was cought");
@@ -113,7 +116,11 @@ mod test {
         if let Some(slack_channels) = fu.channels {
             println!("Found {} channels", slack_channels.len());
             for slack_channel in &slack_channels {
-                println!("{} - {}", &slack_channel.id, &slack_channel.name);
+                println!(
+                    "{} - {}",
+                    &slack_channel.id,
+                    slack_channel.name().unwrap_or_default()
+                );
             }
         }
         assert!(fu.response_metadata.is_some_and(|x| x.next_cursor.eq("")));
@@ -2,6 +2,7 @@ use crate::conversation::{
     errors_str::QueryError,
     messages_str::MessageResponse,
     methods_aggregate::{get_method, ChatHistoryOptions, METHOD},
+    services::slack_request::slack_headers,
 };
 
 pub async fn get_chat_history(
@@ -11,16 +12,7 @@ pub async fn get_chat_history(
     let client = reqwest::Client::new();
     let slack_method = get_method(METHOD::ConversationHistory);
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    let auth_token = std::env::var("SLACK_TOKEN").unwrap();
-    headers.insert(
-        reqwest::header::AUTHORIZATION,
-        format!("Bearer {}", auth_token).parse().unwrap(),
-    );
-    headers.insert(
-        reqwest::header::CONTENT_TYPE,
-        "application/json; charset=utf-8".parse().unwrap(),
-    );
+    let headers = slack_headers();
 
     let mut slack_url = format!("https://slack.com/api/{}", slack_method.action);
     if let Some(query_args) = args {
@@ -33,7 +25,7 @@ pub async fn get_chat_history(
     if let Ok(rexponse) = res {
         response = rexponse.json::<MessageResponse>().await;
     } else {
-        println!("Ohh noo {:?}", res);
+        eprintln!("Ohh noo {:?}", res);
         return Err(QueryError::new(""));
     }
 
@@ -42,7 +34,7 @@ pub async fn get_chat_history(
     if let Ok(bodyx) = body_r {
         body = bodyx;
     } else {
-        println!("Ohh body {:?}", body_r);
+        eprintln!("Ohh body {:?}", body_r);
         return Err(QueryError::new(""));
     }
 
@@ -54,16 +46,7 @@ pub async fn get_chat_reply(
 ) -> Result<MessageResponse, QueryError> {
     let client = reqwest::Client::new();
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    let auth_token = std::env::var("SLACK_TOKEN").unwrap();
-    headers.insert(
-        reqwest::header::AUTHORIZATION,
-        format!("Bearer {}", auth_token).parse().unwrap(),
-    );
-    headers.insert(
-        reqwest::header::CONTENT_TYPE,
-        "application/json; charset=utf-8".parse().unwrap(),
-    );
+    let headers = slack_headers();
 
     let slack_url = format!(
         "https://slack.com/api/{}?{}",
@@ -76,7 +59,7 @@ pub async fn get_chat_reply(
     if let Ok(rexponse) = res {
         response = rexponse.json::<MessageResponse>().await;
     } else {
-        println!("Ohh noo {:?}", res);
+        eprintln!("Ohh noo {:?}", res);
         return Err(QueryError::new(""));
     }
 
@@ -85,13 +68,41 @@ pub async fn get_chat_reply(
     if let Ok(bodyx) = body_r {
         body = bodyx;
     } else {
-        println!("Ohh body {:?}", body_r);
+        eprintln!("Ohh body {:?}", body_r);
         return Err(QueryError::new(""));
     }
 
     Ok(body)
 }
 
+// Messages of a thread, starting with its parent message.
+pub async fn get_thread_replies(
+    history_options: &ChatHistoryOptions,
+) -> Result<MessageResponse, QueryError> {
+    let client = reqwest::Client::new();
+    let headers = slack_headers();
+
+    let slack_url = format!(
+        "https://slack.com/api/{}?{}",
+        get_method(METHOD::Replies).action,
+        history_options.to_query_replies_args()
+    );
+
+    let res = client.get(slack_url).headers(headers).send().await;
+    if let Err(x) = res {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert res to response"));
+    }
+
+    let res_json = res.unwrap().json::<MessageResponse>().await;
+    if let Err(x) = res_json {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert response to json"));
+    }
+
+    Ok(res_json.unwrap())
+}
+
 #[cfg(test)]
 mod test {
     use super::get_chat_history;
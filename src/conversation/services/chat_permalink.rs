@@ -0,0 +1,36 @@
+use crate::conversation::{
+    errors_str::QueryError,
+    ids_str::ChannelId,
+    messages_str::PermalinkResponse,
+    methods_aggregate::{get_method, METHOD},
+    services::slack_request::slack_headers,
+};
+
+pub async fn get_permalink(
+    channel_id: &ChannelId,
+    message_ts: &str,
+) -> Result<PermalinkResponse, QueryError> {
+    let client = reqwest::Client::new();
+    let headers = slack_headers();
+
+    let url = format!(
+        "https://slack.com/api/{}?channel={}&message_ts={}",
+        get_method(METHOD::Permalink).action,
+        channel_id,
+        message_ts
+    );
+
+    let res = client.get(url).headers(headers).send().await;
+    if let Err(x) = res {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert res to response"));
+    }
+
+    let res_json = res.unwrap().json::<PermalinkResponse>().await;
+    if let Err(x) = res_json {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert response to json"));
+    }
+
+    Ok(res_json.unwrap())
+}
@@ -0,0 +1,29 @@
+use crate::conversation::{
+    dnd_str::DndInfoResponse,
+    errors_str::QueryError,
+    methods_aggregate::{get_method, METHOD},
+    services::slack_request::slack_headers,
+};
+
+// Do not disturb status of the token's user.
+pub async fn get_dnd_info() -> Result<DndInfoResponse, QueryError> {
+    let client = reqwest::Client::new();
+    let url = format!(
+        "https://slack.com/api/{}",
+        get_method(METHOD::DndInfo).action
+    );
+
+    let res = client.get(url).headers(slack_headers()).send().await;
+    if let Err(x) = res {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert res to response"));
+    }
+
+    let res_json = res.unwrap().json::<DndInfoResponse>().await;
+    if let Err(x) = res_json {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert response to json"));
+    }
+
+    Ok(res_json.unwrap())
+}
@@ -1,4 +1,17 @@
+pub mod archive_db;
+pub mod channels_cache_fs;
 pub mod chat_channels;
 pub mod chat_history;
-pub mod channels_cache_fs;
+pub mod chat_permalink;
+pub mod dnd_info;
+pub mod notify_command;
+pub mod notify_console;
+pub mod notify_file;
+pub mod notify_mqtt;
+pub mod notify_webhook;
+pub mod slack_probe;
+pub mod slack_request;
+pub mod state_fs;
+pub mod storage_path;
 pub mod users_cache_fs;
+pub mod users_list;
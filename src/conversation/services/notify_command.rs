@@ -0,0 +1,119 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::conversation::{
+    entity::{
+        digest_service::Digest,
+        notification::{Notifier, NotifyEvent},
+    },
+    errors_str::NotifyError,
+};
+
+// Runs a shell command per event, with the event as json on stdin. Killed after `timeout`.
+pub struct CommandNotifier {
+    command: String,
+    timeout: Duration,
+}
+
+impl CommandNotifier {
+    pub fn new(command: &str, timeout_secs: u64) -> CommandNotifier {
+        CommandNotifier {
+            command: command.to_string(),
+            timeout: Duration::from_secs(timeout_secs),
+        }
+    }
+
+    fn run(&self, json: &str, env: (&str, String)) -> Result<(), NotifyError> {
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env(env.0, env.1)
+            .stdin(Stdio::piped())
+            // Stdout is left to the `watch --once` summary
+            .stdout(std::io::stderr())
+            .spawn();
+        if let Err(e) = child {
+            return Err(NotifyError::new(&format!("Failed to start. {}", e)));
+        }
+        let mut child = child.unwrap();
+
+        // A command ignoring stdin closes it early, that is fine. Written aside so one that never reads
+        // can not block past the timeout
+        if let Some(mut stdin) = child.stdin.take() {
+            let json = json.to_string();
+            thread::spawn(move || {
+                let _ = stdin.write_all(json.as_bytes());
+            });
+        }
+
+        let started = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => return Ok(()),
+                Ok(Some(status)) => {
+                    return Err(NotifyError::new(&format!("Exited with {}", status)))
+                }
+                Ok(None) if started.elapsed() >= self.timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(NotifyError::new(&format!(
+                        "Killed after {}s",
+                        self.timeout.as_secs()
+                    )));
+                }
+                Ok(None) => thread::sleep(Duration::from_millis(50)),
+                Err(e) => return Err(NotifyError::new(&e.to_string())),
+            }
+        }
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn name(&self) -> String {
+        format!("command {}", self.command)
+    }
+
+    fn notify(&mut self, event: &NotifyEvent) -> Result<(), NotifyError> {
+        match serde_json::to_string(event) {
+            Ok(json) => self.run(
+                &json,
+                (
+                    "SLACK_EVENT_PRIORITY",
+                    format!("{:?}", event.priority).to_lowercase(),
+                ),
+            ),
+            Err(e) => Err(NotifyError::new(&e.to_string())),
+        }
+    }
+
+    // The digest json on stdin, SLACK_DIGEST=1 tells it apart from events.
+    fn digest(&mut self, digest: &Digest) -> Result<(), NotifyError> {
+        match serde_json::to_string(digest) {
+            Ok(json) => self.run(&json, ("SLACK_DIGEST", "1".into())),
+            Err(e) => Err(NotifyError::new(&e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::CommandNotifier;
+
+    #[test]
+    fn kills_commands_running_too_long() {
+        let started = Instant::now();
+        let result = CommandNotifier::new("sleep 10", 1).run("{}", ("SLACK_DIGEST", "1".into()));
+
+        assert_eq!(result.unwrap_err().to_string(), "Killed after 1s");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(CommandNotifier::new("cat > /dev/null", 1)
+            .run("{}", ("SLACK_DIGEST", "1".into()))
+            .is_ok());
+    }
+}
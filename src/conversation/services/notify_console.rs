@@ -0,0 +1,74 @@
+use crate::conversation::{
+    entity::{
+        digest_service::Digest,
+        mrkdwn_service::MrkdwnRenderer,
+        notification::{Notifier, NotifyEvent, Priority},
+    },
+    errors_str::NotifyError,
+};
+
+// The yellow banner, followed by one line per event.
+pub struct ConsoleNotifier {
+    names: MrkdwnRenderer,
+}
+
+impl ConsoleNotifier {
+    pub fn new() -> ConsoleNotifier {
+        ConsoleNotifier {
+            names: MrkdwnRenderer::from_caches(),
+        }
+    }
+
+    fn line(&self, event: &NotifyEvent) -> String {
+        let color = match event.priority {
+            Priority::Urgent => "\x1b[91m",
+            Priority::Normal => "\x1b[93m",
+            Priority::Low => "\x1b[0m",
+        };
+        let text: String = self.names.to_plain(&event.text).chars().take(80).collect();
+        let link = event
+            .permalink
+            .as_ref()
+            .map(|l| format!("\n  {}", l))
+            .unwrap_or_default();
+
+        format!(
+            "{}[{:?}] {:?} in #{} by {}: {}{}\x1b[0m",
+            color,
+            event.priority,
+            event.reason,
+            if event.channel_name.is_empty() {
+                event.channel_id.as_str()
+            } else {
+                event.channel_name.as_str()
+            },
+            event.sender_label(),
+            text.replace('\n', " "),
+            link
+        )
+    }
+}
+
+impl Notifier for ConsoleNotifier {
+    fn name(&self) -> String {
+        "console".into()
+    }
+
+    fn notify(&mut self, event: &NotifyEvent) -> Result<(), NotifyError> {
+        println!("{}", self.line(event));
+        Ok(())
+    }
+
+    fn notify_all(&mut self, events: &[&NotifyEvent]) -> Result<(), NotifyError> {
+        println!("\x1b[93m-----\n---\n----\nHey! check slack----\n---\n----\n\x1b[0m");
+        for event in events {
+            self.notify(event)?;
+        }
+
+        Ok(())
+    }
+    fn digest(&mut self, digest: &Digest) -> Result<(), NotifyError> {
+        println!("{}", digest.text);
+        Ok(())
+    }
+}
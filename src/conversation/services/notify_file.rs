@@ -0,0 +1,83 @@
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::conversation::{
+    entity::{
+        digest_service::Digest,
+        notification::{Notifier, NotifyEvent},
+    },
+    errors_str::NotifyError,
+    services::storage_path::storage_file,
+};
+
+// Appends every event, and digest, as a json line.
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    // Inside the storage folder when relative.
+    pub fn new(path: &str) -> FileNotifier {
+        FileNotifier {
+            path: storage_file(path),
+        }
+    }
+
+    fn append<T: Serialize>(&self, value: &T) -> Result<(), NotifyError> {
+        let line = match serde_json::to_string(value) {
+            Ok(l) => l,
+            Err(e) => return Err(NotifyError::new(&e.to_string())),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path);
+        if let Err(e) = file {
+            eprintln!("{:?}", e);
+            return Err(NotifyError::new("Failed opening the events file."));
+        }
+        if let Err(e) = writeln!(file.unwrap(), "{}", line) {
+            eprintln!("{:?}", e);
+            return Err(NotifyError::new("Failed to write the event."));
+        }
+
+        Ok(())
+    }
+}
+
+impl Notifier for FileNotifier {
+    fn name(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+
+    fn notify(&mut self, event: &NotifyEvent) -> Result<(), NotifyError> {
+        self.append(event)
+    }
+
+    fn digest(&mut self, digest: &Digest) -> Result<(), NotifyError> {
+        self.append(digest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conversation::entity::notification::Priority;
+
+    #[test]
+    fn appends_json_lines() {
+        let path = std::env::temp_dir().join(format!("events_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let event = NotifyEvent::sample("1720428655.000200", Priority::Urgent);
+
+        let mut notifier = FileNotifier::new(path.to_str().unwrap());
+        notifier.notify_all(&[&event, &event]).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written.lines().count(), 2);
+        assert!(written.starts_with("{\"priority\":\"urgent\",\"reason\":\"direct_mention\""));
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use rumqttc::{Client, Connection, Event, MqttOptions, Outgoing, QoS};
+
+use crate::config::MqttConfig;
+use crate::conversation::{
+    entity::{
+        digest_service::Digest,
+        notification::{Notifier, NotifyEvent, NotifyState},
+    },
+    errors_str::NotifyError,
+};
+
+// Publishes waiting for the connection before they are dropped
+const QUEUE_SIZE: usize = 100;
+// How long close() waits for the queue to be sent
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct MqttNotifier {
+    client: Client,
+    qos: QoS,
+    state_topic: String,
+    event_topic: String,
+    digest_topic: String,
+    // Signaled when the connection thread ends
+    stopped: Receiver<()>,
+}
+
+impl MqttNotifier {
+    // Connects in the background, publishes are queued meanwhile.
+    pub fn connect(config: &MqttConfig) -> MqttNotifier {
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+
+        let (client, connection) = Client::new(options, QUEUE_SIZE);
+        let (stop_tx, stopped) = mpsc::channel();
+        let broker = format!("{}:{}", config.host, config.port);
+        thread::spawn(move || {
+            MqttNotifier::run_connection(connection, &broker);
+            let _ = stop_tx.send(());
+        });
+
+        MqttNotifier {
+            client,
+            qos: MqttNotifier::qos(config.qos),
+            state_topic: config.state_topic.clone(),
+            event_topic: config.event_topic.clone(),
+            digest_topic: config.digest_topic.clone(),
+            stopped,
+        }
+    }
+
+    fn qos(level: u8) -> QoS {
+        match level {
+            0 => QoS::AtMostOnce,
+            2 => QoS::ExactlyOnce,
+            _ => QoS::AtLeastOnce,
+        }
+    }
+
+    // Iterating the connection is what sends, receives and reconnects.
+    fn run_connection(mut connection: Connection, broker: &str) {
+        let mut connected = true;
+        for notification in connection.iter() {
+            match notification {
+                Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                Ok(_) => connected = true,
+                Err(e) => {
+                    // Reported once per outage, not on every retry
+                    if connected {
+                        eprintln!("\x1b[93mMqtt broker {} unreachable. {}\x1b[0m", broker, e);
+                        connected = false;
+                    }
+                    thread::sleep(Duration::from_secs(5));
+                }
+            }
+        }
+    }
+
+    fn publish(&self, topic: &str, retain: bool, payload: String) -> Result<(), NotifyError> {
+        match self.client.try_publish(topic, self.qos, retain, payload) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(NotifyError::new(&format!("Mqtt publish failed. {}", e))),
+        }
+    }
+}
+
+impl Notifier for MqttNotifier {
+    fn name(&self) -> String {
+        format!("mqtt {}", self.event_topic)
+    }
+
+    fn notify(&mut self, event: &NotifyEvent) -> Result<(), NotifyError> {
+        match serde_json::to_string(event) {
+            Ok(json) => self.publish(&self.event_topic, false, json),
+            Err(e) => Err(NotifyError::new(&e.to_string())),
+        }
+    }
+
+    fn state(&mut self, state: &NotifyState) -> Result<(), NotifyError> {
+        match serde_json::to_string(state) {
+            Ok(json) => self.publish(&self.state_topic, true, json),
+            Err(e) => Err(NotifyError::new(&e.to_string())),
+        }
+    }
+
+    fn digest(&mut self, digest: &Digest) -> Result<(), NotifyError> {
+        match serde_json::to_string(digest) {
+            Ok(json) => self.publish(&self.digest_topic, false, json),
+            Err(e) => Err(NotifyError::new(&e.to_string())),
+        }
+    }
+
+    fn close(&mut self) {
+        // The disconnect goes after whatever is queued
+        if self.client.try_disconnect().is_ok() {
+            let _ = self.stopped.recv_timeout(CLOSE_TIMEOUT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conversation::entity::notification::Priority;
+
+    // Runs against a local broker, ie. `mosquitto -p 1883`, when MQTT_TEST_BROKER=host:port is set.
+    #[test]
+    fn publishes_events_and_retained_state() {
+        let broker = match std::env::var("MQTT_TEST_BROKER") {
+            Ok(b) => b,
+            Err(_) => return,
+        };
+        let (host, port) = broker.split_once(':').unwrap_or((&broker, "1883"));
+        let config = MqttConfig {
+            host: host.into(),
+            port: port.parse().unwrap(),
+            client_id: format!("slack-notifier-test-{}", std::process::id()),
+            state_topic: "slack/notifier/test/state".into(),
+            event_topic: "slack/notifier/test/events".into(),
+            ..MqttConfig::default()
+        };
+
+        let mut listener_options =
+            MqttOptions::new("slack-notifier-test-listener", host, config.port);
+        listener_options.set_keep_alive(Duration::from_secs(5));
+        let (listener, mut connection) = Client::new(listener_options, 10);
+        listener
+            .subscribe("slack/notifier/test/#", QoS::AtLeastOnce)
+            .unwrap();
+        let (received_tx, received) = mpsc::channel();
+        thread::spawn(move || {
+            for notification in connection.iter() {
+                if let Ok(Event::Incoming(rumqttc::Packet::Publish(p))) = notification {
+                    let _ = received_tx.send((p.topic, p.retain));
+                }
+            }
+        });
+        thread::sleep(Duration::from_millis(500));
+
+        let event = NotifyEvent::sample("1720428655.000200", Priority::Urgent);
+        let mut notifier = MqttNotifier::connect(&config);
+        notifier.notify(&event).unwrap();
+        notifier
+            .state(&NotifyState::from_events(&[&event], 0))
+            .unwrap();
+        notifier.close();
+
+        let mut topics = vec![
+            received.recv_timeout(Duration::from_secs(5)).unwrap().0,
+            received.recv_timeout(Duration::from_secs(5)).unwrap().0,
+        ];
+        topics.sort();
+        assert_eq!(
+            topics,
+            vec!["slack/notifier/test/events", "slack/notifier/test/state"]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, KeyInit, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::config::WebhookConfig;
+use crate::conversation::{
+    entity::{
+        digest_service::Digest,
+        notification::{Notifier, NotifyEvent, Priority, Reason},
+    },
+    errors_str::NotifyError,
+    ids_str::{ChannelId, UserId},
+    services::storage_path::storage_file,
+};
+
+static SIGNATURE_HEADER: &str = "X-Slack-Notifier-Signature";
+static TIMESTAMP_HEADER: &str = "X-Slack-Notifier-Timestamp";
+static EVENT_ID_HEADER: &str = "X-Slack-Notifier-Event-Id";
+
+// What receivers get, one POST per event.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    // Same event, same id
+    pub id: String,
+    pub priority: Priority,
+    pub reason: Reason,
+    pub reasons: Vec<Reason>,
+    pub channel_id: ChannelId,
+    pub channel_name: String,
+    pub sender: Option<UserId>,
+    pub sender_name: Option<String>,
+    pub text: String,
+    pub ts: String,
+    pub thread_ts: Option<String>,
+    pub permalink: Option<String>,
+    pub deep_link: Option<String>,
+}
+
+impl WebhookPayload {
+    pub fn new(event: &NotifyEvent, snippet_chars: usize) -> WebhookPayload {
+        WebhookPayload {
+            id: event.id(),
+            priority: event.priority,
+            reason: event.reason,
+            reasons: event.reasons.clone(),
+            channel_id: event.channel_id.clone(),
+            channel_name: event.channel_name.clone(),
+            sender: event.sender.clone(),
+            sender_name: event.sender_name.clone(),
+            text: event.text.chars().take(snippet_chars).collect(),
+            ts: event.ts.clone(),
+            thread_ts: event.thread_ts.clone(),
+            permalink: event.permalink.clone(),
+            deep_link: event.deep_link.clone(),
+        }
+    }
+}
+
+// A queued POST, an event or a digest.
+struct Outgoing {
+    id: String,
+    body: String,
+}
+
+// A delivery that ran out of retries.
+#[derive(Debug, Serialize)]
+struct DeliveryFailure<'a> {
+    failed_at: u64,
+    url: &'a str,
+    attempts: u32,
+    error: String,
+    payload: serde_json::Value,
+}
+
+// `v1=<hex>` over `v1:<timestamp>:<body>`, receivers recompute it with the shared secret.
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac = match Hmac::<Sha256>::new_from_slice(secret.as_bytes()) {
+        Ok(m) => m,
+        // Hmac takes keys of any size
+        Err(_) => return String::new(),
+    };
+    mac.update(format!("v1:{}:{}", timestamp, body).as_bytes());
+
+    format!("v1={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+struct Delivery {
+    urls: Vec<String>,
+    secret: Option<String>,
+    retries: u32,
+    backoff: Duration,
+    failures_file: PathBuf,
+    client: reqwest::blocking::Client,
+}
+
+impl Delivery {
+    fn run(self, queue: Receiver<Outgoing>) {
+        for outgoing in queue {
+            for url in self.urls.iter() {
+                if let Err((attempts, error)) = self.post(url, &outgoing.id, &outgoing.body) {
+                    self.record_failure(url, attempts, error, &outgoing);
+                }
+            }
+        }
+    }
+
+    // Retries with an exponential backoff. Client errors other than 429 are not retried.
+    fn post(&self, url: &str, event_id: &str, body: &str) -> Result<(), (u32, String)> {
+        let mut attempts = 0;
+        let mut backoff = self.backoff;
+
+        loop {
+            attempts += 1;
+            let timestamp = now();
+            let mut request = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(EVENT_ID_HEADER, event_id)
+                .body(body.to_string());
+            if let Some(secret) = &self.secret {
+                request = request.header(SIGNATURE_HEADER, sign(secret, timestamp, body));
+            }
+
+            let (error, retryable) = match request.send() {
+                Ok(r) if r.status().is_success() => return Ok(()),
+                Ok(r) => (
+                    format!("Status {}", r.status()),
+                    r.status().is_server_error() || r.status().as_u16() == 429,
+                ),
+                Err(e) => (e.to_string(), true),
+            };
+            if !retryable || attempts > self.retries {
+                return Err((attempts, error));
+            }
+
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+
+    fn record_failure(&self, url: &str, attempts: u32, error: String, outgoing: &Outgoing) {
+        eprintln!(
+            "\x1b[93mWebhook {} failed after {} attempts. {}\x1b[0m",
+            url, attempts, error
+        );
+        let failure = DeliveryFailure {
+            failed_at: now(),
+            url,
+            attempts,
+            error,
+            payload: serde_json::from_str(&outgoing.body).unwrap_or_default(),
+        };
+        let line = match serde_json::to_string(&failure) {
+            Ok(l) => l,
+            Err(_) => return,
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.failures_file);
+        match file {
+            Ok(mut f) => {
+                if let Err(e) = writeln!(f, "{}", line) {
+                    eprintln!("{:?}", e);
+                }
+            }
+            Err(e) => eprintln!("{:?}", e),
+        }
+    }
+}
+
+// Posts from a background thread, so slow receivers and retries never stall a cycle.
+pub struct WebhookNotifier {
+    queue: Option<Sender<Outgoing>>,
+    worker: Option<JoinHandle<()>>,
+    snippet_chars: usize,
+}
+
+impl WebhookNotifier {
+    pub fn start(config: &WebhookConfig) -> WebhookNotifier {
+        let urls = config.urls.clone();
+        let secret = config.signing_secret();
+        let retries = config.retries;
+        let backoff = Duration::from_millis(config.backoff_ms);
+        let timeout = Duration::from_secs(config.timeout_secs);
+        let failures_file = storage_file(&config.failures_file);
+
+        let (queue, received) = mpsc::channel();
+        // The blocking client can not be built inside the tokio runtime
+        let worker = thread::spawn(move || {
+            let client = reqwest::blocking::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default();
+            let delivery = Delivery {
+                urls,
+                secret,
+                retries,
+                backoff,
+                failures_file,
+                client,
+            };
+            delivery.run(received)
+        });
+
+        WebhookNotifier {
+            queue: Some(queue),
+            worker: Some(worker),
+            snippet_chars: config.snippet_chars,
+        }
+    }
+}
+
+impl WebhookNotifier {
+    fn send<T: Serialize>(&self, id: String, payload: &T) -> Result<(), NotifyError> {
+        let queue = match &self.queue {
+            Some(q) => q,
+            None => return Err(NotifyError::new("Webhook notifier closed")),
+        };
+        let body = match serde_json::to_string(payload) {
+            Ok(b) => b,
+            Err(e) => return Err(NotifyError::new(&e.to_string())),
+        };
+
+        match queue.send(Outgoing { id, body }) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(NotifyError::new("Webhook delivery thread stopped")),
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> String {
+        "webhook".into()
+    }
+
+    fn notify(&mut self, event: &NotifyEvent) -> Result<(), NotifyError> {
+        self.send(event.id(), &WebhookPayload::new(event, self.snippet_chars))
+    }
+
+    // Same headers, its id is `digest:<until>`.
+    fn digest(&mut self, digest: &Digest) -> Result<(), NotifyError> {
+        self.send(format!("digest:{}", digest.until), digest)
+    }
+
+    // Waits for the queued events, retries included.
+    fn close(&mut self) {
+        self.queue = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn now() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(n) => n.as_secs(),
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    #[test]
+    fn signs_the_timestamp_and_body() {
+        assert_eq!(
+            sign("secret", 1720428655, "{}"),
+            "v1=026c718af61ef7da726455974c3c4bd65776b234019aae627605e0662f80686c"
+        );
+        assert_ne!(
+            sign("secret", 1720428655, "{}"),
+            sign("secret", 1720428656, "{}")
+        );
+        assert_ne!(
+            sign("secret", 1720428655, "{}"),
+            sign("other", 1720428655, "{}")
+        );
+    }
+
+    // Answers with the given statuses, keeping the requests.
+    fn serve(responses: Vec<u16>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(l) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = l.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                requests.push(format!("{}\r\n{}", head, String::from_utf8(body).unwrap()));
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn retries_signs_and_records_failures() {
+        let (url, server) = serve(vec![500, 200, 400]);
+        let failures_file =
+            std::env::temp_dir().join(format!("webhook_failures_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&failures_file);
+        let config = WebhookConfig {
+            urls: vec![url],
+            secret: Some("secret".into()),
+            backoff_ms: 10,
+            failures_file: failures_file.display().to_string(),
+            ..WebhookConfig::default()
+        };
+
+        let mut notifier = WebhookNotifier::start(&config);
+        notifier
+            .notify(&NotifyEvent::sample("1720428655.000200", Priority::Urgent))
+            .unwrap();
+        notifier
+            .notify(&NotifyEvent::sample("1720428700.000200", Priority::Normal))
+            .unwrap();
+        notifier.close();
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        let (head, body) = requests[1].split_once("\r\n\r\n").unwrap();
+        let header = |name: &str| {
+            head.lines()
+                .find_map(|l| l.strip_prefix(&format!("{}: ", name.to_lowercase())))
+                .unwrap()
+                .to_string()
+        };
+        let timestamp: u64 = header(TIMESTAMP_HEADER).parse().unwrap();
+        assert_eq!(header(SIGNATURE_HEADER), sign("secret", timestamp, body));
+        assert_eq!(
+            header(EVENT_ID_HEADER),
+            "c07bsnu3gg1:1720428655.000200:directmention"
+        );
+        assert!(body.contains("\"text\":\"prod down\""));
+
+        // The 400 is not retried and ends in the failures file
+        let failures = std::fs::read_to_string(&failures_file).unwrap();
+        let _ = std::fs::remove_file(&failures_file);
+        assert_eq!(failures.lines().count(), 1);
+        assert!(failures.contains("1720428700.000200"));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::conversation::{
+    auth_str::{ApiStatus, AuthTestResponse},
+    errors_str::QueryError,
+    methods_aggregate::{get_method, METHOD},
+    services::slack_request::slack_headers,
+};
+
+// Identity of the token and the scopes granted to it.
+pub async fn get_auth_test() -> Result<(AuthTestResponse, Vec<String>), QueryError> {
+    let client = reqwest::Client::new();
+    let url = format!(
+        "https://slack.com/api/{}",
+        get_method(METHOD::AuthTest).action
+    );
+
+    let res = client.get(url).headers(slack_headers()).send().await;
+    if let Err(x) = res {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert res to response"));
+    }
+    let response = res.unwrap();
+
+    let scopes: Vec<String> = match response.headers().get("x-oauth-scopes") {
+        Some(header) => header
+            .to_str()
+            .unwrap_or("")
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let res_json = response.json::<AuthTestResponse>().await;
+    if let Err(x) = res_json {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert response to json"));
+    }
+
+    Ok((res_json.unwrap(), scopes))
+}
+
+// Calls a method only to learn if the token may use it.
+pub async fn probe_method(method: METHOD, query: &str) -> Result<ApiStatus, QueryError> {
+    let client = reqwest::Client::new();
+    let url = format!(
+        "https://slack.com/api/{}?{}",
+        get_method(method).action,
+        query
+    );
+
+    let res = client.get(url).headers(slack_headers()).send().await;
+    if let Err(x) = res {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert res to response"));
+    }
+
+    let res_json = res.unwrap().json::<ApiStatus>().await;
+    if let Err(x) = res_json {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert response to json"));
+    }
+
+    Ok(res_json.unwrap())
+}
@@ -0,0 +1,34 @@
+use std::sync::OnceLock;
+
+use reqwest::header::HeaderMap;
+
+static SLACK_TOKEN: OnceLock<String> = OnceLock::new();
+
+// Token to use instead of the SLACK_TOKEN variable. Only the first call has effect.
+pub fn set_token(token: String) {
+    let _ = SLACK_TOKEN.set(token);
+}
+
+fn token() -> String {
+    match SLACK_TOKEN.get() {
+        Some(t) => t.clone(),
+        // Slack answers not_authed when it is missing
+        None => std::env::var("SLACK_TOKEN").unwrap_or_default(),
+    }
+}
+
+// Headers every slack web api call needs.
+pub fn slack_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let auth_token = token();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {}", auth_token).parse().unwrap(),
+    );
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        "application/json; charset=utf-8".parse().unwrap(),
+    );
+
+    headers
+}
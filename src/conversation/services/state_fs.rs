@@ -0,0 +1,52 @@
+use std::fs;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::conversation::{
+    errors_str::FileSystemError,
+    services::storage_path::{storage_dir, storage_file},
+};
+
+// Reads a json state file from the storage folder, the default when it does not exist yet.
+pub fn read_state<T: DeserializeOwned + Default>(name: &str) -> Result<T, FileSystemError> {
+    let content = match fs::read_to_string(storage_file(name)) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => {
+            eprintln!("{:?}", e);
+            return Err(FileSystemError::new(&format!("Error opening {}", name)));
+        }
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(state) => Ok(state),
+        Err(e) => Err(FileSystemError::new(&format!(
+            "Error reading {}. {}",
+            name, e
+        ))),
+    }
+}
+
+// Replaces the state file, writing to a temporary one first so it is never half written.
+pub fn write_state<T: Serialize>(name: &str, state: &T) -> Result<(), FileSystemError> {
+    if let Err(e) = fs::create_dir_all(storage_dir()) {
+        eprintln!("{:?}", e);
+        return Err(FileSystemError::new("Error creating storage folder"));
+    }
+
+    let content = match serde_json::to_string_pretty(state) {
+        Ok(c) => c,
+        Err(e) => return Err(FileSystemError::new(&e.to_string())),
+    };
+    let temporary = storage_file(&format!("{}.tmp", name));
+    if let Err(e) = fs::write(&temporary, content) {
+        eprintln!("{:?}", e);
+        return Err(FileSystemError::new(&format!("Failed to write {}", name)));
+    }
+    if let Err(e) = fs::rename(&temporary, storage_file(name)) {
+        eprintln!("{:?}", e);
+        return Err(FileSystemError::new(&format!("Failed to write {}", name)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::conversation::errors_str::FileSystemError;
+
+static STORAGE_ENV: &str = "SLACK_STORAGE_DIR";
+static DEFAULT_STORAGE: &str = "static/storage";
+
+static STORAGE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// Overrides the folder with the cache files. Only the first call has effect.
+pub fn set_storage_dir(dir: PathBuf) {
+    let _ = STORAGE_DIR.set(dir);
+}
+
+// The --storage-dir flag, then SLACK_STORAGE_DIR, then static/storage.
+pub fn storage_dir() -> PathBuf {
+    STORAGE_DIR
+        .get_or_init(|| match std::env::var(STORAGE_ENV) {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(DEFAULT_STORAGE),
+        })
+        .clone()
+}
+
+pub fn storage_file(name: &str) -> PathBuf {
+    storage_dir().join(name)
+}
+
+// Result of reading a cache file line by line without skipping anything.
+#[derive(Debug, Clone)]
+pub struct CacheCheck {
+    pub entries: usize,
+    // Lines the cache readers would ignore or misread
+    pub problems: Vec<String>,
+}
+
+// A cache file field, quoted when it holds a comma or a quote, quotes doubled.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// The fields of a cache file line, unquoting those written by `csv_field`.
+pub fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+// Checks a comma separated cache file with a header line.
+pub fn check_csv(
+    name: &str,
+    columns: usize,
+    valid_id: fn(&str) -> bool,
+    bool_columns: &[usize],
+) -> Result<CacheCheck, FileSystemError> {
+    let content = match std::fs::read_to_string(storage_file(name)) {
+        Ok(c) => c,
+        Err(e) => return Err(FileSystemError::new(&e.to_string())),
+    };
+
+    let mut check = CacheCheck {
+        entries: 0,
+        problems: Vec::new(),
+    };
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv(line);
+        if !valid_id(&fields[0]) {
+            // The header line
+            if i == 0 {
+                continue;
+            }
+            check
+                .problems
+                .push(format!("line {}: unexpected id {}", i + 1, fields[0]));
+            continue;
+        }
+        if fields.len() < columns {
+            check.problems.push(format!(
+                "line {}: expected {} columns, found {}",
+                i + 1,
+                columns,
+                fields.len()
+            ));
+            continue;
+        }
+        for c in bool_columns {
+            if fields[*c] != "true" && fields[*c] != "false" {
+                check.problems.push(format!(
+                    "line {}: column {} should be true or false",
+                    i + 1,
+                    c + 1
+                ));
+            }
+        }
+        check.entries += 1;
+    }
+
+    Ok(check)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{csv_field, split_csv};
+
+    #[test]
+    fn quotes_fields_with_commas() {
+        let line = format!(
+            "U04853SN1AP,{},{},true",
+            csv_field("Lopez, Ana"),
+            csv_field("say \"hi\"")
+        );
+        assert_eq!(line, r#"U04853SN1AP,"Lopez, Ana","say ""hi""",true"#);
+        assert_eq!(
+            split_csv(&line),
+            vec!["U04853SN1AP", "Lopez, Ana", "say \"hi\"", "true"]
+        );
+        assert_eq!(
+            split_csv("U0000000002,ana,false"),
+            vec!["U0000000002", "ana", "false"]
+        );
+    }
+}
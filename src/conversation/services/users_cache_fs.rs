@@ -1,17 +1,24 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{BufRead, BufReader},
 };
 
-use crate::conversation::{entity::users::User, errors_str::FileSystemError};
+use crate::conversation::{
+    entity::users::User,
+    errors_str::FileSystemError,
+    ids_str::UserId,
+    services::storage_path::{
+        check_csv, csv_field, split_csv, storage_dir, storage_file, CacheCheck,
+    },
+};
 
-// static FILE_PATH: &'static str = "static/storage";
-static FILE_NAME: &'static str = "static/storage/users_cache.txt";
+static FILE_NAME: &str = "users_cache.txt";
+static FILE_HEADER: &str = "slackUserId,Name,should_follow";
 
 pub fn read_cache() -> Result<Vec<User>, FileSystemError> {
-    let file = File::open(FILE_NAME);
+    let file = File::open(storage_file(FILE_NAME));
     if let Err(error) = file {
-        println!("{:?}", error);
+        eprintln!("{:?}", error);
         return Err(FileSystemError::new("Error opening file. {:?}"));
     }
     let file = file.unwrap();
@@ -21,18 +28,48 @@ pub fn read_cache() -> Result<Vec<User>, FileSystemError> {
     let reader = BufReader::new(file);
     for line in reader.lines() {
         if let Err(e) = line {
-            println!("error load users from csv {:?}", e);
+            eprintln!("error load users from csv {:?}", e);
             continue;
         }
         let l = line.unwrap();
 
-        let user_line: Vec<&str> = l.split(",").collect();
-        if user_line.is_empty() || !user_line[0].starts_with("U") {
+        let user_line = split_csv(&l);
+        // Skips the header and anything that is not a user id
+        let Ok(user_id) = UserId::parse(&user_line[0]) else {
             continue;
-        }
-        let user = User::new(user_line[0], user_line[1], user_line[2] == "true");
+        };
+        let user = User::new(
+            user_id,
+            user_line.get(1).map(|n| n.as_str()).unwrap_or(""),
+            user_line.get(2).is_some_and(|f| f == "true"),
+        );
         slack_users.push(user);
     }
 
     return Ok(slack_users);
 }
+
+// Replaces the cache file contents.
+pub fn write_cache(users: &[User]) -> Result<(), FileSystemError> {
+    if let Err(e) = fs::create_dir_all(storage_dir()) {
+        eprintln!("{:?}", e);
+        return Err(FileSystemError::new("Error creating storage folder"));
+    }
+
+    let mut lines = vec![FILE_HEADER.to_string()];
+    lines.extend(
+        users
+            .iter()
+            .map(|u| format!("{},{},{}", u.id(), csv_field(u.name()), u.should_follow)),
+    );
+    if let Err(e) = fs::write(storage_file(FILE_NAME), lines.join("\n")) {
+        eprintln!("{:?}", e);
+        return Err(FileSystemError::new("Failed to write users cache."));
+    }
+
+    Ok(())
+}
+
+pub fn check_cache() -> Result<CacheCheck, FileSystemError> {
+    check_csv(FILE_NAME, 3, |id| UserId::parse(id).is_ok(), &[2])
+}
@@ -0,0 +1,60 @@
+use crate::conversation::{
+    errors_str::QueryError,
+    ids_str::UserId,
+    methods_aggregate::{get_method, METHOD},
+    services::slack_request::slack_headers,
+    users_str::{UserInfoResponse, UsersResponse},
+};
+
+pub async fn get_users_list(paginate: Option<&str>) -> Result<UsersResponse, QueryError> {
+    let client = reqwest::Client::new();
+    let headers = slack_headers();
+
+    let mut url = format!(
+        "https://slack.com/api/{}?limit=200",
+        get_method(METHOD::Users).action
+    );
+    if let Some(paginate_cursor) = paginate {
+        url.push_str(&format!("&cursor={}", paginate_cursor));
+    }
+
+    let res = client.get(url).headers(headers).send().await;
+    if let Err(x) = res {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert res to response"));
+    }
+
+    let res_json = res.unwrap().json::<UsersResponse>().await;
+    if let Err(x) = res_json {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert response to json"));
+    }
+
+    Ok(res_json.unwrap())
+}
+
+// A single user, for ids missing from the cache.
+pub async fn get_user_info(user_id: &UserId) -> Result<UserInfoResponse, QueryError> {
+    let client = reqwest::Client::new();
+    let headers = slack_headers();
+
+    let url = format!(
+        "https://slack.com/api/{}?user={}",
+        get_method(METHOD::UserInfo).action,
+        user_id
+    );
+
+    let res = client.get(url).headers(headers).send().await;
+    if let Err(x) = res {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert res to response"));
+    }
+
+    let res_json = res.unwrap().json::<UserInfoResponse>().await;
+    if let Err(x) = res_json {
+        eprintln!("{:?}", x);
+        return Err(QueryError::new("Query convert response to json"));
+    }
+
+    Ok(res_json.unwrap())
+}
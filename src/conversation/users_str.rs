@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+use super::channels_str::PaginationMetadata;
+use super::ids_str::UserId;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UsersResponse {
+    pub ok: bool,
+    pub members: Option<Vec<SlackUser>>,
+    pub error: Option<String>,
+    pub needed: Option<String>,
+    pub provided: Option<String>,
+    pub response_metadata: Option<PaginationMetadata>,
+}
+
+// A member of the workspace
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SlackUser {
+    pub id: UserId,
+    // Username, without spaces
+    pub name: Option<String>,
+    pub real_name: Option<String>,
+    pub deleted: Option<bool>,
+    pub is_bot: Option<bool>,
+    // Time zone, ie. Europe/Madrid
+    pub tz: Option<String>,
+    pub profile: Option<UserProfile>,
+}
+
+impl SlackUser {
+    // Name as shown in the slack client
+    pub fn display_name(&self) -> String {
+        let profile_name = self
+            .profile
+            .as_ref()
+            .and_then(|p| p.display_name.clone())
+            .filter(|n| !n.is_empty());
+
+        profile_name
+            .or(self.real_name.clone())
+            .or(self.name.clone())
+            .unwrap_or(self.id.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UserProfile {
+    pub display_name: Option<String>,
+    pub real_name: Option<String>,
+    // Avatar url, 72px
+    pub image_72: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserInfoResponse {
+    pub ok: bool,
+    pub user: Option<SlackUser>,
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{UserInfoResponse, UsersResponse};
+
+    #[test]
+    fn parses_members() {
+        let serialized = "{
+            \"ok\": true,
+            \"members\": [{
+                \"id\": \"U04853SN1AP\",
+                \"name\": \"ana\",
+                \"real_name\": \"Ana Lopez\",
+                \"deleted\": false,
+                \"is_bot\": false,
+                \"profile\": {\"display_name\": \"\", \"real_name\": \"Ana Lopez\"}
+            }],
+            \"response_metadata\": {\"next_cursor\": \"\"}
+        }";
+        let users: UsersResponse = serde_json::from_str(serialized).unwrap();
+
+        assert_eq!(users.members.unwrap()[0].display_name(), "Ana Lopez");
+    }
+
+    #[test]
+    fn parses_user_info() {
+        let serialized = "{
+            \"ok\": true,
+            \"user\": {
+                \"id\": \"W04853SN1AP\",
+                \"name\": \"ana\",
+                \"tz\": \"Europe/Madrid\",
+                \"profile\": {\"display_name\": \"ana.l\", \"image_72\": \"https://avatars.slack-edge.com/ana_72.png\"}
+            }
+        }";
+        let info: UserInfoResponse = serde_json::from_str(serialized).unwrap();
+        let user = info.user.unwrap();
+
+        assert_eq!(user.display_name(), "ana.l");
+        assert_eq!(user.tz.as_deref(), Some("Europe/Madrid"));
+    }
+}